@@ -0,0 +1,27 @@
+// a crt look for --shader: a curved screen, scanlines and a darkened border. the rest of the
+// shader, the screen texture and r_locals, is prepended by the emulator
+
+const CURVATURE: f32 = 0.08;
+const SCANLINE_STRENGTH: f32 = 0.25;
+const VIGNETTE_STRENGTH: f32 = 0.35;
+
+@fragment
+fn fs_main(@location(0) tex_coord: vec2<f32>) -> @location(0) vec4<f32> {
+    // bend the screen outwards from the middle
+    let centered = tex_coord * 2.0 - 1.0;
+    let bent = centered * (1.0 + CURVATURE * dot(centered.yx, centered.yx));
+    let uv = bent * 0.5 + 0.5;
+    // sampled before the branch, texture samples need uniform control flow
+    var color = textureSample(r_tex_color, r_tex_sampler, uv).rgb;
+    if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+
+    // a dark line every other row of window pixels, rolling slowly down the screen
+    let row = uv.y * r_locals.size.y + r_locals.time * 4.0;
+    color *= 1.0 - SCANLINE_STRENGTH * (0.5 + 0.5 * sin(row * 3.14159));
+
+    let edge = uv * (1.0 - uv);
+    color *= mix(1.0, pow(edge.x * edge.y * 16.0, 0.25), VIGNETTE_STRENGTH);
+    return vec4<f32>(color, 1.0);
+}
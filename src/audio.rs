@@ -0,0 +1,107 @@
+use crate::frontend::Sound;
+
+const BEEP_FREQUENCY: f32 = 110.0; // hz
+const BEEP_VOLUME: f32 = 0.10;
+const AUDIO_PATTERN_BITS: f32 = 128.0; // samples in an xo-chip audio pattern
+
+// an audio output that plays what the frontend is told to, whatever library it goes through
+pub trait Beeper {
+    fn play(&mut self, sound: &Sound);
+}
+
+// a square wave, or a looped 1-bit sample pattern once the game provides one. the output keeps
+// running and ramps towards the gate, so beeps can fade in and out
+#[derive(Debug, Clone)]
+pub struct Synth {
+    sample_rate: f32,
+    phase_inc: f32,
+    phase: f32,
+    pattern: Option<[u8; 16]>,
+    volume: f32,
+    gain: f32,
+    attack_step: f32,  // gain per sample while the gate is open
+    release_step: f32, // gain per sample while the gate is closed
+    gate: bool,
+}
+impl Synth {
+    // the beep fades in and out over the attack and release times, in milliseconds
+    pub fn new(sample_rate: f32, attack_ms: f32, release_ms: f32) -> Self {
+        let samples_per_ms = sample_rate / 1000.0;
+        Self {
+            sample_rate,
+            phase_inc: BEEP_FREQUENCY / sample_rate,
+            phase: 0.0,
+            pattern: None,
+            volume: BEEP_VOLUME,
+            gain: 0.0,
+            attack_step: 1.0 / (attack_ms * samples_per_ms),
+            release_step: 1.0 / (release_ms * samples_per_ms),
+            gate: false,
+        }
+    }
+    pub fn set(&mut self, sound: &Sound) {
+        self.gate = sound.on;
+        if let Some(pattern) = sound.pattern {
+            self.pattern = Some(pattern);
+            self.phase_inc = sound.pattern_rate / AUDIO_PATTERN_BITS / self.sample_rate;
+        }
+    }
+    // mono samples
+    pub fn fill(&mut self, out: &mut [f32]) {
+        for x in out.iter_mut() {
+            self.gain = envelope(self.gain, self.gate, self.attack_step, self.release_step);
+            let volume = self.volume * self.gain;
+            let high = match &self.pattern {
+                Some(pattern) => {
+                    let bit = (self.phase * AUDIO_PATTERN_BITS) as usize;
+                    pattern[bit / 8] & (0x80 >> (bit % 8)) != 0
+                }
+                None => self.phase <= 0.5,
+            };
+            *x = if high { volume } else { -volume };
+            self.phase = (self.phase + self.phase_inc) % 1.0;
+        }
+    }
+}
+
+// linear ramp of the gain towards 1.0 while the gate is open and 0.0 while it's closed
+fn envelope(gain: f32, open: bool, attack_step: f32, release_step: f32) -> f32 {
+    if open {
+        (gain + attack_step).min(1.0)
+    } else {
+        (gain - release_step).max(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn envelope_ramps_smoothly_both_ways() {
+        let (attack_step, release_step) = (0.25, 0.5);
+        let mut gain = 0.0;
+        let mut curve = Vec::new();
+        for open in [true; 5].into_iter().chain([false; 3]) {
+            gain = envelope(gain, open, attack_step, release_step);
+            curve.push(gain);
+        }
+        assert_eq!(curve, [0.25, 0.5, 0.75, 1.0, 1.0, 0.5, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn beep_fades_in_over_the_attack_time() {
+        // 1ms of attack at 8khz is 8 samples
+        let mut synth = Synth::new(8000.0, 1.0, 1.0);
+        synth.set(&Sound {
+            on: true,
+            pattern: None,
+            pattern_rate: 4000.0,
+        });
+        let mut out = [0.0; 12];
+        synth.fill(&mut out);
+        let levels = out.map(f32::abs);
+        assert!(levels[..8].windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(levels[7..].iter().all(|&level| level == BEEP_VOLUME));
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{
+    chip8::{
+        BoundsPolicy, Chip8, ColorEffect, KeyPriority, MemorySize, OpcodePolicy, PlanePriority,
+        SysPolicy, FONT_SIZE,
+    },
+    platform::Platform,
+    quirks::Quirks,
+    timing::TimingTable,
+};
+use alloc::boxed::Box;
+use rand::RngCore;
+
+const FRAME_RATE: u32 = 60; // hz, a clock rate is spread evenly over the frames
+
+// configuration for a new machine, anything left out follows the platform.
+// the game is loaded afterwards, since that can fail.
+#[derive(Default)]
+pub struct Chip8Builder {
+    platform: Platform,
+    detect_platform: Option<bool>,
+    memory_size: Option<MemorySize>,
+    quirks: Option<Quirks>,
+    timing: Option<TimingTable>,
+    stack_depth: Option<Option<usize>>,
+    seed: Option<u64>,
+    rng: Option<Box<dyn RngCore + Send>>,
+    boot_garbage: bool,
+    font: Option<[u8; FONT_SIZE]>,
+    font_addr: Option<u16>,
+    key_priority: KeyPriority,
+    sys_policy: SysPolicy,
+    opcode_policy: OpcodePolicy,
+    bounds_policy: BoundsPolicy,
+    color_effect: ColorEffect,
+    plane_priority: PlanePriority,
+    warn_uninit: bool,
+    #[cfg(feature = "std")]
+    opcode_profile: bool,
+    events: bool,
+}
+impl Chip8Builder {
+    // a platform given here isn't replaced by the one detected for known games, unless
+    // detect_platform says so
+    pub fn platform(mut self, platform: Platform) -> Self {
+        self.platform = platform;
+        self.detect_platform.get_or_insert(false);
+        self
+    }
+    // whether loading a known game switches to the platform it was written for, replacing the
+    // quirks, timing and stack depth given here. on unless a platform was given
+    pub fn detect_platform(mut self, enabled: bool) -> Self {
+        self.detect_platform = Some(enabled);
+        self
+    }
+    pub fn memory_size(mut self, memory_size: MemorySize) -> Self {
+        self.memory_size = Some(memory_size);
+        self
+    }
+    pub fn quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = Some(quirks);
+        self
+    }
+    pub fn timing(mut self, timing: TimingTable) -> Self {
+        self.timing = Some(timing);
+        self
+    }
+    // every instruction takes the same time, so this many run per second
+    pub fn clock_hz(self, hz: u32) -> Self {
+        self.timing(TimingTable::per_frame(hz / FRAME_RATE))
+    }
+    // nested calls allowed before the game crashes, none for unlimited
+    pub fn stack_depth(mut self, depth: Option<usize>) -> Self {
+        self.stack_depth = Some(depth);
+        self
+    }
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+    // draws rnd and boot garbage from this generator, instead of a seeded or random one
+    pub fn rng(mut self, rng: impl RngCore + Send + 'static) -> Self {
+        self.rng = Some(Box::new(rng));
+        self
+    }
+    pub fn boot_garbage(mut self, enabled: bool) -> Self {
+        self.boot_garbage = enabled;
+        self
+    }
+    pub fn font(mut self, font: [u8; FONT_SIZE]) -> Self {
+        self.font = Some(font);
+        self
+    }
+    pub fn font_addr(mut self, addr: u16) -> Self {
+        self.font_addr = Some(addr);
+        self
+    }
+    pub fn key_priority(mut self, priority: KeyPriority) -> Self {
+        self.key_priority = priority;
+        self
+    }
+    pub fn sys_policy(mut self, policy: SysPolicy) -> Self {
+        self.sys_policy = policy;
+        self
+    }
+    pub fn opcode_policy(mut self, policy: OpcodePolicy) -> Self {
+        self.opcode_policy = policy;
+        self
+    }
+    pub fn bounds_policy(mut self, policy: BoundsPolicy) -> Self {
+        self.bounds_policy = policy;
+        self
+    }
+    pub fn color_effect(mut self, effect: ColorEffect) -> Self {
+        self.color_effect = effect;
+        self
+    }
+    pub fn plane_priority(mut self, priority: PlanePriority) -> Self {
+        self.plane_priority = priority;
+        self
+    }
+    pub fn warn_uninit(mut self, enabled: bool) -> Self {
+        self.warn_uninit = enabled;
+        self
+    }
+    #[cfg(feature = "std")]
+    pub fn opcode_profile(mut self, enabled: bool) -> Self {
+        self.opcode_profile = enabled;
+        self
+    }
+    // records events for take_events
+    pub fn events(mut self, enabled: bool) -> Self {
+        self.events = enabled;
+        self
+    }
+
+    pub fn build(self) -> Chip8 {
+        let memory_size = self
+            .memory_size
+            .unwrap_or_else(|| self.platform.memory_size());
+        let mut chip8 = Chip8::with_memory_size(self.platform, memory_size);
+        if let Some(quirks) = self.quirks {
+            chip8.set_quirks(quirks);
+        }
+        if let Some(timing) = self.timing {
+            chip8.set_timing(timing);
+        }
+        if let Some(depth) = self.stack_depth {
+            chip8.set_stack_depth(depth);
+        }
+        if let Some(seed) = self.seed {
+            chip8.seed_rng(seed);
+        }
+        if let Some(rng) = self.rng {
+            chip8.set_rng(rng);
+        }
+        if let Some(font) = &self.font {
+            chip8.set_font(font);
+        }
+        if let Some(addr) = self.font_addr {
+            chip8.set_font_addr(addr);
+        }
+        #[cfg(feature = "std")]
+        if self.opcode_profile {
+            chip8.enable_opcode_profile();
+        }
+        if self.events {
+            chip8.enable_events();
+        }
+        chip8.set_detect_platform(self.detect_platform.unwrap_or(true));
+        chip8.set_boot_garbage(self.boot_garbage);
+        chip8.set_key_priority(self.key_priority);
+        chip8.set_sys_policy(self.sys_policy);
+        chip8.set_opcode_policy(self.opcode_policy);
+        chip8.set_bounds_policy(self.bounds_policy);
+        chip8.apply_color_effect(self.color_effect);
+        chip8.set_plane_priority(self.plane_priority);
+        chip8.warn_uninit(self.warn_uninit);
+        chip8
+    }
+}
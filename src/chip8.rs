@@ -1,11 +1,48 @@
+#[cfg(feature = "jit")]
+use crate::jit::{Code, Jit};
+#[cfg(feature = "std")]
+use crate::profile::OpcodeProfile;
+use crate::{
+    builder::Chip8Builder,
+    disasm::{self, ListingEntry},
+    error::Chip8Error,
+    instruction::{self, AluOp, Instruction, Instruction::*},
+    platform::Platform,
+    quirks::Quirks,
+    stack::Stack,
+    state::{Snapshot, StateError},
+    timer::TimerSource,
+    timing::TimingTable,
+};
+use alloc::{boxed::Box, collections::VecDeque, vec, vec::Vec};
+use core::{fmt, str::FromStr};
+use rand::{rngs::StdRng, Rng, RngCore, SeedableRng};
+#[cfg(feature = "std")]
+use std::time::Instant;
+
 // console constants
-const MEMORY_SIZE: usize = 4096; // 4KB
 const REGISTER_COUNT: usize = 16;
-const STACK_SIZE: usize = 16;
-const START_ADDR: usize = 0x0200; // 0..0x0200 is reserved
-const SCREEN_WIDTH: usize = 64; // pixels
-const SCREEN_HEIGHT: usize = 32; // pixels
-const FONT_SPRITES: &[u8] = &[
+pub(crate) const LORES_WIDTH: usize = 64; // pixels
+pub(crate) const HIRES_WIDTH: usize = 128; // pixels, super-chip only
+const HIRES_HEIGHT: usize = 64; // pixels, super-chip only
+pub(crate) const SCREEN_SIZE: usize = HIRES_WIDTH * HIRES_HEIGHT; // pixels, enough for any mode
+const TWO_PAGE_HEIGHT: usize = 64; // pixels, hi-res chip-8 only
+const TWO_PAGE_ENTRY: u16 = 0x02C0; // where hi-res chip-8 games start, past their interpreter patch
+const PLANE_COUNT: u8 = 2; // xo-chip bitplanes, for up to four colors
+const AUDIO_PATTERN_SIZE: usize = 16; // bytes, 128 one bit samples
+const DEFAULT_PITCH: u8 = 64; // plays the pattern at 4000 samples per second
+const RPL_FLAG_COUNT: usize = 16; // super-chip has 8, xo-chip doubled them
+const PC_HISTORY_SIZE: usize = 64; // instructions
+const VBLANK_WAIT: i64 = 1_000_000 / 60; // microseconds, enough to end any frame
+const KEY_COUNT: usize = 16; // per keypad, chip-8x has a second one
+pub(crate) const COLOR_COLUMNS: usize = LORES_WIDTH / 8; // chip-8x colors 8 pixels of a row
+pub(crate) const COLOR_ROWS: usize = 32; // chip-8x only has the vip's screen
+const ZONE_HEIGHT: usize = 4; // rows, the zones bxy0 colors are 8x4 pixels
+const FOREGROUND_COLORS: u8 = 8; // black, red, blue, violet, green, yellow, aqua and white
+const BACKGROUND_COLORS: u8 = 4; // blue, black, green and red
+const BOOT_COLOR: u8 = 1; // red, on the first background, blue
+pub(crate) const FONT_SIZE: usize = 16 * 5; // bytes, a 4x5 sprite per hex digit
+const FONT_SPRITES: [u8; FONT_SIZE] = [
     0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
     0x20, 0x60, 0x20, 0x20, 0x70, // 1
     0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
@@ -23,316 +60,2703 @@ const FONT_SPRITES: &[u8] = &[
     0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
     0xF0, 0x80, 0xF0, 0x80, 0x80, // F
 ];
+const LARGE_FONT_SPRITES: &[u8] = &[
+    0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // 0
+    0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // 1
+    0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // 2
+    0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // 3
+    0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // 5
+    0x3E, 0x7C, 0xE0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // 6
+    0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // 7
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // 8
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C, // 9
+];
+
+// post-processing the frontend applies when presenting the screen, emulation is unaffected
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorEffect {
+    // phosphor-like fade of unlit pixels
+    #[default]
+    None,
+    // exponential decay of unlit pixels, lower decays slower
+    Ghosting {
+        decay: f32,
+    },
+    // glow around lit pixels
+    Neon {
+        bloom_radius: u8,
+    },
+    // invert the screen for a frame whenever a draw collides
+    InvertOnCollision,
+}
+
+// the color of xo-chip pixels lit on both planes, emulation is unaffected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PlanePriority {
+    // a color of their own, the fourth of the palette
+    #[default]
+    Blend,
+    // the first plane's color, drawn over the second
+    Plane1,
+    // the second plane's color, drawn over the first
+    Plane2,
+}
+impl PlanePriority {
+    // the palette entry for a pixel with these planes lit, 0 for none, 1 and 2 for either plane
+    // and 3 for both
+    pub fn color_index(self, planes: u8) -> u8 {
+        match (self, planes & 3) {
+            (PlanePriority::Plane1, 3) => 1,
+            (PlanePriority::Plane2, 3) => 2,
+            (_, lit) => lit,
+        }
+    }
+}
+impl FromStr for PlanePriority {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "blend" => Ok(PlanePriority::Blend),
+            "1" => Ok(PlanePriority::Plane1),
+            "2" => Ok(PlanePriority::Plane2),
+            _ => Err(()),
+        }
+    }
+}
+
+// how much memory the machine has, addresses wrap around at the end of it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MemorySize {
+    #[default]
+    Kb4,
+    Kb8,
+    Kb64,
+}
+impl MemorySize {
+    pub fn bytes(self) -> usize {
+        match self {
+            MemorySize::Kb4 => 0x1000,
+            MemorySize::Kb8 => 0x2000,
+            MemorySize::Kb64 => 0x10000,
+        }
+    }
+}
+impl FromStr for MemorySize {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "4k" => Ok(MemorySize::Kb4),
+            "8k" => Ok(MemorySize::Kb8),
+            "64k" => Ok(MemorySize::Kb64),
+            _ => Err(()),
+        }
+    }
+}
+
+// which key ld vx k takes when several are pressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KeyPriority {
+    // the lowest held key, scanning the keypad like the vip
+    #[default]
+    Lowest,
+    // the held key that was pressed last
+    MostRecent,
+    // every press in the order it happened, even if the key was released since. presses of
+    // keys released before the wait started don't count
+    PressOrder,
+}
+impl FromStr for KeyPriority {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lowest" => Ok(KeyPriority::Lowest),
+            "recent" => Ok(KeyPriority::MostRecent),
+            "queue" => Ok(KeyPriority::PressOrder),
+            _ => Err(()),
+        }
+    }
+}
+
+// a register used as a source before anything was written to it since reset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UninitRead {
+    pub pc: u16,
+    pub register: u8,
+}
+
+// called with the address of a sys nnn, the registers and memory
+pub type SysCallback = Box<dyn FnMut(u16, &mut [u8; REGISTER_COUNT], &mut [u8]) + Send>;
+
+// called around every instruction that runs with its address and the decoded instruction, or
+// the opcode if it's unknown. it gets the whole machine, so it can read and change anything
+pub type InstructionHook = Box<dyn FnMut(&mut Chip8, u16, Result<Instruction, u16>) + Send>;
+
+// what sys nnn does. it ran machine code on the original hardware, which isn't emulated.
+#[derive(Default)]
+pub enum SysPolicy {
+    // skip the call, it's reported through take_sys_calls
+    #[default]
+    Ignore,
+    // stop like 00fd, the call is reported the same way
+    Halt,
+    // hand the call to the frontend, e.g. for experimenting with hybrid roms
+    Callback(SysCallback),
+}
+
+// a sys nnn the policy ignored or halted on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SysCall {
+    pub pc: u16,
+    pub addr: u16,
+}
+
+// an instruction the platform doesn't have, usually from a corrupt rom or data run as code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnknownOpcode {
+    pub pc: u16,
+    pub opcode: u16,
+}
+impl fmt::Display for UnknownOpcode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown opcode {:04x} at 0x{:03x}", self.opcode, self.pc)
+    }
+}
+impl core::error::Error for UnknownOpcode {}
+
+// what an unknown opcode does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OpcodePolicy {
+    // crash with the opcode as the error
+    #[default]
+    Error,
+    // step over it, it's reported through take_unknown_opcodes
+    Skip,
+    // stop like a game that ended, keeping the screen. it's reported the same way
+    Halt,
+}
+impl FromStr for OpcodePolicy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(OpcodePolicy::Error),
+            "skip" => Ok(OpcodePolicy::Skip),
+            "halt" => Ok(OpcodePolicy::Halt),
+            _ => Err(()),
+        }
+    }
+}
+
+// what reads and writes past the end of memory through i do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BoundsPolicy {
+    // continue at the start of memory, like i itself wraps around
+    #[default]
+    Wrap,
+    // crash with the address as the error
+    Error,
+}
+impl FromStr for BoundsPolicy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wrap" => Ok(BoundsPolicy::Wrap),
+            "error" => Ok(BoundsPolicy::Error),
+            _ => Err(()),
+        }
+    }
+}
+
+// what a tick did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickResult {
+    // where the instruction was, or where the machine stands if nothing ran
+    pub pc: u16,
+    // none when nothing ran, because the machine halted or waits for the next frame
+    pub opcode: Option<u16>,
+    pub cost: i64, // microseconds
+    pub display_changed: bool,
+}
+
+// what step_frame did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameSummary {
+    pub instructions: u32,
+    pub display_changed: bool,
+    // whether the sound timer ran at the end of the frame, before the timers advanced, so even a
+    // sound timer of 1 is heard
+    pub tone: bool,
+    // microseconds the last instruction ran past the budget, to take off the next one
+    pub overrun: i64,
+}
 
+// something that happened while running, for frontends and debuggers that react to changes
+// instead of polling the whole machine every frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    ScreenCleared,
+    // at the wrapped start coordinates, height in rows
+    SpriteDrawn { x: u8, y: u8, height: u8 },
+    SoundStarted,
+    SoundStopped,
+    // ld vx k started waiting for a key
+    WaitingForKey,
+}
+
+// why the machine stopped running instructions, it stays stopped until it's reset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Halt {
+    // the game asked to exit with 00fd
+    Exit,
+    // a machine code call stopped the game under SysPolicy::Halt
+    SysCall,
+    // the game crashed, tick returns the error until the machine is reset
+    Crash(Chip8Error),
+    // the game jumped to itself, which many games do once they're done, or ran an unknown
+    // opcode under OpcodePolicy::Halt. the screen is final
+    Ended,
+}
+
+// the colors of chip-8x's vp-590 color board, by index. lit pixels take the foreground color of
+// the 8 pixels of the row they're in, everything else the background color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ColorMap {
+    #[cfg_attr(feature = "serde", serde(with = "big_array"))]
+    pub foreground: [u8; COLOR_COLUMNS * COLOR_ROWS], // row major, one per 8 pixels
+    pub background: u8,
+}
+impl ColorMap {
+    // the foreground color of the pixel at x, y
+    pub fn color_at(&self, x: usize, y: usize) -> u8 {
+        self.foreground[y * COLOR_COLUMNS + x / 8]
+    }
+}
+impl Default for ColorMap {
+    fn default() -> Self {
+        Self {
+            foreground: [BOOT_COLOR; COLOR_COLUMNS * COLOR_ROWS],
+            background: 0,
+        }
+    }
+}
+
+// where rnd draws come from, recorded draws can be replayed to reproduce a run exactly
+#[derive(Default)]
+enum RngTape {
+    #[default]
+    Off,
+    Recording(Vec<u8>),
+    Replaying(alloc::vec::IntoIter<u8>),
+}
+
+#[derive(Clone, Copy)]
+enum HookPoint {
+    Pre,
+    Post,
+}
+
+// the whole machine, configuration included. with the serde feature it serializes everything
+// but the frontend's hooks and pending reports, and the random generator, which is reseeded
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Chip8 {
-    mem: [u8; MEMORY_SIZE],
+    mem: Box<[u8]>, // always a power of two
+    // the instruction at each address and its cost, decoded the first time it runs there.
+    // writes to memory drop the entries they touch, new timing drops them all, and empty means
+    // nothing is decoded
+    #[cfg_attr(feature = "serde", serde(skip))]
+    decoded: Vec<Option<(Result<Instruction, u16>, i64)>>,
+    #[cfg(feature = "jit")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    jit: Option<Box<Jit>>,
     v: [u8; REGISTER_COUNT],
     ir: u16,
     pc: u16,
     dt: u8,
     st: u8,
-    stack: Vec<u16>,
-    pub screen: [bool; SCREEN_WIDTH * SCREEN_HEIGHT],
-    pub keypad: [bool; 16],
+    stack: Stack,
+    #[cfg_attr(feature = "serde", serde(with = "big_array"))]
+    font: [u8; FONT_SIZE],
+    font_addr: u16,            // the large font follows right after the small one
+    rpl: [u8; RPL_FLAG_COUNT], // hp48 user flags, they outlive resets like they outlived the game
+    pc_history: VecDeque<u16>,
+    rom: Vec<u8>,
+    pixels_drawn_since_clear: usize,
+    ticks: u64,
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    profile: Option<Box<OpcodeProfile>>,
+    color_effect: ColorEffect,
+    plane_priority: PlanePriority,
+    collided: bool,
+    warn_uninit: bool,
+    written: u16, // bitmask of registers written since reset
+    warned: u16,  // bitmask of registers already reported
+    #[cfg_attr(feature = "serde", serde(skip))]
+    uninit_reads: Vec<UninitRead>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    sys_policy: SysPolicy,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    sys_calls: Vec<SysCall>,
+    opcode_policy: OpcodePolicy,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    unknown_opcodes: Vec<UnknownOpcode>,
+    bounds_policy: BoundsPolicy,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    timer_source: Option<Box<dyn TimerSource>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pre_hooks: Vec<InstructionHook>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    post_hooks: Vec<InstructionHook>,
+    #[cfg_attr(feature = "serde", serde(skip, default = "entropy_rng"))]
+    rng: Box<dyn RngCore + Send>,
+    seed: Option<u64>, // restarts the same random sequence on every cold boot
+    #[cfg_attr(feature = "serde", serde(skip))]
+    custom_rng: bool, // set_rng's generator, never replaced on boot
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rng_tape: RngTape,
+    boot_garbage: bool,
+    hires: bool,
+    two_page: bool, // hi-res chip-8, 64x64 pixels over two display pages
+    platform: Platform,
+    detect_platform: bool, // switch to the platform of known games when they're loaded
+    quirks: Quirks,
+    timing: TimingTable,
+    vblank_wait: bool,
+    halt: Option<Halt>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    events: Option<Vec<Event>>, // none unless the frontend asked for them
+    #[cfg_attr(feature = "serde", serde(skip))]
+    display_changed: bool, // since the start of the tick
+    waiting_for_key: bool,
+    key_wait: Option<u8>, // the key ld vx k saw pressed, waiting for its release
+    key_priority: KeyPriority,
+    presses: VecDeque<u8>, // keys in the order they were pressed, see KeyPriority
+    releases: u16,         // bitmask of keys released since ld vx k started waiting on them
+    audio_pattern: Option<[u8; AUDIO_PATTERN_SIZE]>, // xo-chip, replaces the beep once loaded
+    pitch: u8,
+    planes: u8, // bitmask of the planes drawing and clearing affect
+    // a row of bits per plane for each line, pixels past the active resolution are unused
+    #[cfg_attr(feature = "serde", serde(with = "big_array"))]
+    screen: [Row; HIRES_HEIGHT],
+    #[cfg_attr(feature = "serde", serde(skip))]
+    dirty_rows: u64, // bitmask of the screen rows changed since take_dirty_rows
+    colors: ColorMap, // chip-8x only
+    keypad: [bool; KEY_COUNT],
+    second_keypad: [bool; KEY_COUNT], // chip-8x only
 }
 impl Chip8 {
+    // configures a machine option by option, see Chip8Builder
+    pub fn builder() -> Chip8Builder {
+        Chip8Builder::default()
+    }
+    // a cosmac vip machine with its quirks
     pub fn new() -> Self {
+        Self::with_platform(Platform::default())
+    }
+    // a machine emulating the platform's interpreter, with its quirks, timing, screen and memory
+    pub fn with_platform(platform: Platform) -> Self {
+        Self::with_memory_size(platform, platform.memory_size())
+    }
+    // like with_platform, but with memory of another size than the platform had
+    pub fn with_memory_size(platform: Platform, memory_size: MemorySize) -> Self {
         let mut chip8 = Self {
-            mem: [0; MEMORY_SIZE],
+            mem: vec![0; memory_size.bytes()].into_boxed_slice(),
+            decoded: Vec::new(),
+            #[cfg(feature = "jit")]
+            jit: None,
             v: [0u8; REGISTER_COUNT],
-            stack: vec![0; STACK_SIZE],
-            keypad: [false; 16],
-            screen: [false; SCREEN_WIDTH * SCREEN_HEIGHT],
+            stack: Stack::with_depth(platform.stack_depth()),
+            font: FONT_SPRITES,
+            font_addr: 0,
+            rpl: [0; RPL_FLAG_COUNT],
+            pc_history: VecDeque::with_capacity(PC_HISTORY_SIZE),
+            rom: Vec::new(),
+            pixels_drawn_since_clear: 0,
+            ticks: 0,
+            #[cfg(feature = "std")]
+            profile: None,
+            color_effect: ColorEffect::None,
+            plane_priority: PlanePriority::Blend,
+            collided: false,
+            warn_uninit: false,
+            written: 0,
+            warned: 0,
+            uninit_reads: Vec::new(),
+            sys_policy: SysPolicy::Ignore,
+            sys_calls: Vec::new(),
+            opcode_policy: OpcodePolicy::Error,
+            unknown_opcodes: Vec::new(),
+            bounds_policy: BoundsPolicy::Wrap,
+            timer_source: None,
+            pre_hooks: Vec::new(),
+            post_hooks: Vec::new(),
+            rng: entropy_rng(),
+            seed: None,
+            custom_rng: false,
+            rng_tape: RngTape::Off,
+            boot_garbage: false,
+            hires: false,
+            two_page: false,
+            platform,
+            detect_platform: true,
+            quirks: platform.quirks(),
+            timing: platform.timing(),
+            vblank_wait: false,
+            halt: None,
+            events: None,
+            display_changed: false,
+            waiting_for_key: false,
+            key_wait: None,
+            key_priority: KeyPriority::Lowest,
+            presses: VecDeque::with_capacity(16),
+            releases: 0,
+            audio_pattern: None,
+            pitch: DEFAULT_PITCH,
+            planes: 1,
+            keypad: [false; KEY_COUNT],
+            second_keypad: [false; KEY_COUNT],
+            screen: [Row::default(); HIRES_HEIGHT],
+            dirty_rows: u64::MAX,
+            colors: ColorMap::default(),
             ir: 0,
-            pc: START_ADDR as u16,
+            pc: platform.start_addr() as u16,
             dt: 0,
             st: 0,
         };
-        chip8.mem[..FONT_SPRITES.len()].copy_from_slice(FONT_SPRITES); // setup fonts in memory
+        chip8.write_fonts();
         chip8
     }
-    pub fn load(&mut self, game: &[u8]) {
-        self.mem[START_ADDR..(START_ADDR + game.len())].copy_from_slice(game);
+    // known games switch to the platform they were written for first, see set_detect_platform
+    pub fn load(&mut self, game: &[u8]) -> Result<(), Chip8Error> {
+        if let Some(platform) = Platform::detect(game).filter(|_| self.detect_platform) {
+            self.switch_platform(platform);
+        }
+        self.load_rom(game)
+    }
+    // switches to xo-chip and loads the game, whatever platform it's detected as. xo-chip games
+    // can reach all 64k of memory, so the machine has to be built with that much
+    pub fn load_xo_chip(&mut self, game: &[u8]) -> Result<(), Chip8Error> {
+        let required = MemorySize::Kb64.bytes();
+        if self.mem.len() < required {
+            return Err(Chip8Error::MemoryTooSmall {
+                size: self.mem.len(),
+                required,
+            });
+        }
+        self.set_platform(Platform::XoChip);
+        self.load_rom(game)
+    }
+    // hi-res chip-8 games are recognized by the jump into their interpreter patch they start with
+    // games have to fit between the platform's start address and the end of memory, anything
+    // larger is likely not a game for this platform, or not a game at all
+    fn load_rom(&mut self, game: &[u8]) -> Result<(), Chip8Error> {
+        let start = self.platform.start_addr();
+        let max = self.mem.len() - start;
+        if game.len() > max {
+            return Err(Chip8Error::RomTooLarge {
+                size: game.len(),
+                max,
+                start,
+            });
+        }
+        self.rom = game.to_vec();
+        self.two_page = game.starts_with(&[0x12, 0x60]);
+        self.pc = self.platform.start_addr() as u16;
+        self.write_rom();
+        Ok(())
+    }
+    // restores the power-on state with the same game loaded, reseeding the random generator
+    // and filling the rest of memory with garbage if boot garbage is enabled
+    pub fn cold_boot(&mut self) {
+        #[cfg(feature = "tracing")]
+        tracing::info!("cold boot");
+        self.power_cycle();
+        match self.seed {
+            Some(seed) => self.rng = Box::new(StdRng::seed_from_u64(seed)),
+            None if !self.custom_rng => self.rng = entropy_rng(),
+            None => {}
+        }
+        if self.boot_garbage {
+            self.rng.fill(&mut self.mem[..]);
+            self.decoded.clear();
+            self.write_fonts();
+        }
+        self.write_rom();
+    }
+    // resets the cpu and display but leaves memory and the random sequence as they are
+    pub fn warm_reset(&mut self) {
+        #[cfg(feature = "tracing")]
+        tracing::info!("warm reset");
+        let mem = self.mem.clone();
+        self.power_cycle();
+        self.mem = mem;
+        self.decoded.clear();
+    }
+    // replaces the built-in hex digit sprites, e.g. with the style of another interpreter
+    pub fn set_font(&mut self, font: &[u8; FONT_SIZE]) {
+        self.font = *font;
+        self.write_fonts();
     }
-    pub fn tick(&mut self) -> i64 {
+    // where the fonts are in memory, interpreters put them anywhere below the game, e.g. 0x50.
+    // the old location keeps its bytes until the next cold boot.
+    pub fn set_font_addr(&mut self, addr: u16) {
+        self.font_addr = addr & self.address_mask();
+        self.write_fonts();
+    }
+    pub fn set_boot_garbage(&mut self, enabled: bool) {
+        self.boot_garbage = enabled;
+    }
+    // runs one instruction, the result says which and how long it took
+    pub fn tick(&mut self) -> Result<TickResult, Chip8Error> {
+        let idle = TickResult {
+            pc: self.pc,
+            opcode: None,
+            cost: VBLANK_WAIT,
+            display_changed: false,
+        };
+        // a halted game runs nothing until it's reset, the frame just passes
+        match self.halt {
+            Some(Halt::Crash(e)) => return Err(e),
+            Some(_) => return Ok(idle),
+            None => {}
+        }
+        // with the display wait quirk, nothing runs after a draw until the next frame
+        if self.vblank_wait {
+            return Ok(idle);
+        }
+
+        if self.pc_history.len() == PC_HISTORY_SIZE {
+            self.pc_history.pop_front();
+        }
+        self.pc_history.push_back(self.pc);
+        self.ticks += 1;
+        self.display_changed = false;
+
+        let pc = self.pc;
         let op = self.fetch();
+        if self.warn_uninit {
+            self.check_uninit(op);
+        }
+        let (decoded, cost) = self.decode_at(pc, op);
+        if !self.pre_hooks.is_empty() {
+            self.run_hooks(HookPoint::Pre, pc, decoded);
+        }
 
-        self.execute(op)
+        // timing every instruction isn't free, so only do it when asked to
+        #[cfg(not(feature = "std"))]
+        let result = self.execute(op, decoded);
+        #[cfg(feature = "std")]
+        let result = if self.profile.is_none() {
+            self.execute(op, decoded)
+        } else {
+            let start = Instant::now();
+            let result = self.execute(op, decoded);
+            let elapsed = start.elapsed();
+            if let Some(profile) = &mut self.profile {
+                profile.record(op, elapsed);
+            }
+            result
+        };
+        if let Err(e) = result {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(pc = %format_args!("{pc:03x}"), error = %e, "crashed");
+            self.crash(Halt::Crash(e));
+            return Err(e);
+        }
+        if !self.post_hooks.is_empty() {
+            self.run_hooks(HookPoint::Post, pc, decoded);
+        }
+        #[cfg(feature = "tracing")]
+        {
+            tracing::trace!(
+                pc = %format_args!("{pc:03x}"),
+                opcode = %format_args!("{op:04x}"),
+                "{}",
+                crate::disasm::disassemble(op)
+            );
+            if let Some(halt) = self.halt {
+                tracing::info!(pc = %format_args!("{pc:03x}"), ?halt, "halted");
+            }
+        }
+        Ok(TickResult {
+            pc,
+            opcode: Some(op),
+            cost,
+            display_changed: self.display_changed,
+        })
+    }
+    // runs instructions until they used up the budget, then advances the timers by one frame
+    pub fn step_frame(&mut self, budget_us: i64) -> Result<FrameSummary, Chip8Error> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("step_frame", budget_us).entered();
+        let mut frame = FrameSummary {
+            instructions: 0,
+            display_changed: false,
+            tone: false,
+            overrun: 0,
+        };
+        let mut budget = budget_us;
+        while budget > 0 {
+            #[cfg(feature = "jit")]
+            if let Some((instructions, cost)) = self.run_compiled(budget) {
+                budget -= cost;
+                frame.instructions += instructions;
+                continue;
+            }
+            let tick = self.tick()?;
+            // nothing runs for the rest of a frame spent halted or waiting for vblank
+            if tick.opcode.is_none() {
+                budget = 0;
+                break;
+            }
+            budget -= tick.cost;
+            frame.instructions += 1;
+            frame.display_changed |= tick.display_changed;
+        }
+        frame.overrun = -budget;
+        frame.tone = self.tone();
+        self.timers();
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            instructions = frame.instructions,
+            display_changed = frame.display_changed,
+            tone = frame.tone,
+            overrun = frame.overrun,
+            "frame done"
+        );
+        Ok(frame)
     }
+    // advances the timers by one frame, or as many as the timer source reports
     pub fn timers(&mut self) {
-        self.dt = self.dt.saturating_sub(1);
-        self.st = self.st.saturating_sub(1);
+        let frames = self
+            .timer_source
+            .as_mut()
+            .map_or(1, |source| source.elapsed_frames());
+        let frames = frames.min(u8::MAX as u32) as u8;
+        self.vblank_wait = false;
+        self.dt = self.dt.saturating_sub(frames);
+        let tone = self.tone();
+        self.st = self.st.saturating_sub(frames);
+        self.sound_changed(tone);
+    }
+    pub fn platform(&self) -> Platform {
+        self.platform
+    }
+    // switches to the platform's quirks, timing and screen right away, games move to its load
+    // address on the next cold boot
+    pub fn set_platform(&mut self, platform: Platform) {
+        self.platform = platform;
+        self.quirks = platform.quirks();
+        self.timing = platform.timing();
+        self.stack.set_depth(platform.stack_depth());
+        self.recompile();
+    }
+    // whether loading a known game switches to the platform it was written for, replacing the
+    // quirks, timing and stack depth set before
+    pub fn set_detect_platform(&mut self, enabled: bool) {
+        self.detect_platform = enabled;
+    }
+    // nested calls allowed before the game crashes, none for unlimited
+    pub fn set_stack_depth(&mut self, depth: Option<usize>) {
+        self.stack.set_depth(depth);
+    }
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+        self.recompile();
+    }
+    // the platform these quirks are from, none for a mix of them
+    pub fn quirk_preset(&self) -> Option<Platform> {
+        Platform::QUIRK_PRESETS
+            .into_iter()
+            .find(|platform| platform.quirks() == self.quirks)
+    }
+    // switches to the next platform's quirks while the game runs, to watch a glitch come and go
+    // and find out which interpreter the game was written for. a mix starts over at the first.
+    // only the quirks change, the platform stays, so the instructions, memory, resolution and
+    // planes the game set up carry on as they were and a drw waiting for the next frame still
+    // gets it
+    pub fn cycle_quirks(&mut self) -> Platform {
+        let presets = Platform::QUIRK_PRESETS;
+        let next = presets
+            .iter()
+            .position(|platform| platform.quirks() == self.quirks)
+            .map_or(0, |i| (i + 1) % presets.len());
+        self.set_quirks(presets[next].quirks());
+        presets[next]
+    }
+    // microseconds each instruction takes, which decides how many run per frame
+    pub fn set_timing(&mut self, timing: TimingTable) {
+        self.timing = timing;
+        self.recompile();
+    }
+    pub fn set_timer_source(&mut self, source: Box<dyn TimerSource>) {
+        self.timer_source = Some(source);
+    }
+    // whether the game exited, crashed or ended, the frontend should shut down or reset
+    pub fn halted(&self) -> bool {
+        self.halt.is_some()
+    }
+    pub fn halt(&self) -> Option<Halt> {
+        self.halt
+    }
+    // why the game crashed, if it did
+    pub fn error(&self) -> Option<Chip8Error> {
+        match self.halt {
+            Some(Halt::Crash(e)) => Some(e),
+            _ => None,
+        }
+    }
+    // the return addresses and stack pointer, for debuggers
+    pub fn stack(&self) -> &Stack {
+        &self.stack
     }
     pub fn tone(&self) -> bool {
         self.st > 0
     }
+    // the xo-chip sample pattern to loop while the tone plays, none means the plain beep
+    pub fn audio_pattern(&self) -> Option<&[u8; AUDIO_PATTERN_SIZE]> {
+        self.audio_pattern.as_ref()
+    }
+    // samples of the pattern per second
+    pub fn pattern_rate(&self) -> f32 {
+        #[cfg(feature = "std")]
+        return 4000.0 * 2f32.powf((self.pitch as f32 - 64.0) / 48.0);
+        // powf needs std, so step by 2^(1/48) once per pitch away from 64 instead
+        #[cfg(not(feature = "std"))]
+        {
+            const STEP: f32 = 1.014_545_3;
+            let step = if self.pitch >= 64 { STEP } else { 1.0 / STEP };
+            (0..self.pitch.abs_diff(64)).fold(4000.0, |rate, _| rate * step)
+        }
+    }
+    // number of instructions executed, regardless of their cycle cost
+    pub fn ticks_elapsed(&self) -> u64 {
+        self.ticks
+    }
+    pub fn reset_tick_counter(&mut self) {
+        self.ticks = 0;
+    }
+    // starts recording events for take_events
+    pub fn enable_events(&mut self) {
+        self.events.get_or_insert_with(Vec::new);
+    }
+    pub fn take_events(&mut self) -> Vec<Event> {
+        self.events
+            .as_mut()
+            .map(core::mem::take)
+            .unwrap_or_default()
+    }
+    #[cfg(feature = "std")]
+    pub fn enable_opcode_profile(&mut self) {
+        self.profile.get_or_insert_with(Default::default);
+    }
+    #[cfg(feature = "std")]
+    pub fn opcode_profile(&self) -> Option<&OpcodeProfile> {
+        self.profile.as_deref()
+    }
+    pub fn apply_color_effect(&mut self, effect: ColorEffect) {
+        self.color_effect = effect;
+    }
+    pub fn color_effect(&self) -> ColorEffect {
+        self.color_effect
+    }
+    pub fn set_plane_priority(&mut self, priority: PlanePriority) {
+        self.plane_priority = priority;
+    }
+    pub fn plane_priority(&self) -> PlanePriority {
+        self.plane_priority
+    }
+    // whether a draw set vf since the last call, meant to be called once per frame
+    pub fn take_collision(&mut self) -> bool {
+        core::mem::take(&mut self.collided)
+    }
+    // tracks registers that haven't been written since reset and reports reads of them,
+    // for catching roms that rely on the initial register state
+    pub fn warn_uninit(&mut self, enabled: bool) {
+        self.warn_uninit = enabled;
+    }
+    pub fn take_uninit_reads(&mut self) -> Vec<UninitRead> {
+        core::mem::take(&mut self.uninit_reads)
+    }
+    pub fn set_key_priority(&mut self, priority: KeyPriority) {
+        self.key_priority = priority;
+    }
+    pub fn set_sys_policy(&mut self, policy: SysPolicy) {
+        self.sys_policy = policy;
+    }
+    pub fn take_sys_calls(&mut self) -> Vec<SysCall> {
+        core::mem::take(&mut self.sys_calls)
+    }
+    // runs the hook before every instruction, after it's fetched, e.g. for breakpoints or cheats
+    // that hold a register at a value
+    pub fn add_pre_hook(
+        &mut self,
+        hook: impl FnMut(&mut Chip8, u16, Result<Instruction, u16>) + Send + 'static,
+    ) {
+        self.pre_hooks.push(Box::new(hook));
+    }
+    // runs the hook after every instruction that didn't crash, e.g. for coverage or tracing
+    pub fn add_post_hook(
+        &mut self,
+        hook: impl FnMut(&mut Chip8, u16, Result<Instruction, u16>) + Send + 'static,
+    ) {
+        self.post_hooks.push(Box::new(hook));
+    }
+    pub fn clear_hooks(&mut self) {
+        self.pre_hooks.clear();
+        self.post_hooks.clear();
+    }
+    // compiles straight runs of register arithmetic to native code the first time they run,
+    // and runs those instead of interpreting them one by one. code the game writes over goes
+    // back to the interpreter. it steps aside while hooks, the opcode profile or uninitialized
+    // register warnings watch single instructions, and tick always interprets
+    #[cfg(feature = "jit")]
+    pub fn enable_jit(&mut self) -> Result<(), String> {
+        if self.jit.is_none() {
+            self.jit = Some(Box::new(Jit::new()?));
+        }
+        Ok(())
+    }
+    #[cfg(feature = "jit")]
+    pub fn disable_jit(&mut self) {
+        self.jit = None;
+    }
+    pub fn set_opcode_policy(&mut self, policy: OpcodePolicy) {
+        self.opcode_policy = policy;
+    }
+    pub fn take_unknown_opcodes(&mut self) -> Vec<UnknownOpcode> {
+        core::mem::take(&mut self.unknown_opcodes)
+    }
+    pub fn set_bounds_policy(&mut self, policy: BoundsPolicy) {
+        self.bounds_policy = policy;
+    }
+    // makes rnd and boot garbage draw the same numbers every run, starting over on each boot
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.seed = Some(seed);
+        self.custom_rng = false;
+        self.rng = Box::new(StdRng::seed_from_u64(seed));
+    }
+    // rnd and boot garbage draw from this generator instead, e.g. one that returns chosen values
+    // in tests. it carries on across boots, unlike a seed
+    pub fn set_rng(&mut self, rng: impl RngCore + Send + 'static) {
+        self.seed = None;
+        self.custom_rng = true;
+        self.rng = Box::new(rng);
+    }
+    pub fn record_rng(&mut self) {
+        self.rng_tape = RngTape::Recording(Vec::new());
+    }
+    pub fn recorded_rng(&self) -> &[u8] {
+        match &self.rng_tape {
+            RngTape::Recording(draws) => draws,
+            _ => &[],
+        }
+    }
+    // feeds rnd these exact values in order, running out of them is an error
+    pub fn replay_rng(&mut self, draws: Vec<u8>) {
+        self.rng_tape = RngTape::Replaying(draws.into_iter());
+    }
+    // the rpl user flags games save data like high scores in, for keeping between runs
+    pub fn rpl_flags(&self) -> &[u8] {
+        &self.rpl
+    }
+    // restores saved flags, extra bytes are ignored
+    pub fn set_rpl_flags(&mut self, flags: &[u8]) {
+        let len = flags.len().min(RPL_FLAG_COUNT);
+        self.rpl[..len].copy_from_slice(&flags[..len]);
+    }
+    // everything a running game can change, in a versioned binary format. configuration like the
+    // platform, quirks and timing isn't included, so a state loads back into a machine set up the
+    // same way, usually for the same game. a machine halted on an error that doesn't come from
+    // running a game, like one deserialized that way, can't be saved
+    pub fn save_state(&self) -> Result<Vec<u8>, Chip8Error> {
+        let state = Snapshot {
+            mem: self.mem.to_vec(),
+            v: self.v,
+            ir: self.ir,
+            pc: self.pc,
+            dt: self.dt,
+            st: self.st,
+            stack: self.stack.frames().collect(),
+            rpl: self.rpl,
+            hires: self.hires,
+            two_page: self.two_page,
+            planes: self.planes,
+            screen: self.screen_bytes(),
+            keypad: self.keypad,
+            second_keypad: self.second_keypad,
+            key_wait: self.key_wait,
+            releases: self.releases,
+            presses: self.presses.iter().copied().collect(),
+            waiting_for_key: self.waiting_for_key,
+            vblank_wait: self.vblank_wait,
+            audio_pattern: self.audio_pattern,
+            pitch: self.pitch,
+            colors: self.colors,
+            halt: self.halt,
+        };
+        Ok(state.encode()?)
+    }
+    // restores a state from save_state, a state that doesn't load leaves the machine as it was
+    pub fn load_state(&mut self, bytes: &[u8]) -> Result<(), Chip8Error> {
+        let state = Snapshot::decode(bytes)?;
+        let mut stack = Stack::with_depth(self.stack.depth());
+        for &addr in &state.stack {
+            stack.push(addr).map_err(|_| StateError::Invalid)?;
+        }
+
+        self.mem = state.mem.into();
+        self.decoded.clear();
+        self.v = state.v;
+        (self.ir, self.pc, self.dt, self.st) = (state.ir, state.pc, state.dt, state.st);
+        self.stack = stack;
+        self.rpl = state.rpl;
+        (self.hires, self.two_page) = (state.hires, state.two_page);
+        self.planes = state.planes;
+        let (width, height) = self.dimensions();
+        self.screen = [Row::default(); HIRES_HEIGHT];
+        for (i, &pixel) in state.screen[..width * height].iter().enumerate() {
+            let row = &mut self.screen[i / width];
+            for plane in planes(pixel) {
+                row[plane] |= column(i % width);
+            }
+        }
+        self.dirty_rows = u64::MAX;
+        (self.keypad, self.second_keypad) = (state.keypad, state.second_keypad);
+        (self.key_wait, self.releases) = (state.key_wait, state.releases);
+        self.presses = state.presses.into();
+        (self.waiting_for_key, self.vblank_wait) = (state.waiting_for_key, state.vblank_wait);
+        self.audio_pattern = state.audio_pattern;
+        self.pitch = state.pitch;
+        self.colors = state.colors;
+        self.halt = state.halt;
+        self.display_changed = true;
+        Ok(())
+    }
+    // presses are recorded as they happen, so ld vx k sees every press and release even when
+    // both land between two ticks. keys 0x10 to 0x1f are chip-8x's second keypad, which only
+    // skp2 and sknp2 read, and keys past those are ignored
+    pub fn key_down(&mut self, key: usize) {
+        if let Some(held) = self.second_key(key) {
+            *held = true;
+            return;
+        }
+        match self.keypad.get_mut(key) {
+            Some(held) if !*held => *held = true,
+            _ => return,
+        }
+        if self.presses.len() == self.keypad.len() {
+            self.presses.pop_front();
+        }
+        self.presses.push_back(key as u8);
+    }
+    // forgets the press unless every press counts
+    pub fn key_up(&mut self, key: usize) {
+        if let Some(held) = self.second_key(key) {
+            *held = false;
+            return;
+        }
+        match self.keypad.get_mut(key) {
+            Some(held) if *held => *held = false,
+            _ => return,
+        }
+        self.releases |= 1 << key;
+        if self.key_priority != KeyPriority::PressOrder {
+            self.presses.retain(|&pressed| pressed as usize != key);
+        }
+    }
+    pub fn keypad(&self) -> &[bool; KEY_COUNT] {
+        &self.keypad
+    }
+    pub fn second_keypad(&self) -> &[bool; KEY_COUNT] {
+        &self.second_keypad
+    }
+    // the colors of the screen on chip-8x, none elsewhere
+    pub fn colors(&self) -> Option<&ColorMap> {
+        (self.platform == Platform::Chip8X).then_some(&self.colors)
+    }
+    // read-only views of the machine state, for debuggers and tests
+    pub fn registers(&self) -> &[u8; REGISTER_COUNT] {
+        &self.v
+    }
+    pub fn index(&self) -> u16 {
+        self.ir
+    }
+    pub fn pc(&self) -> u16 {
+        self.pc
+    }
+    pub fn delay_timer(&self) -> u8 {
+        self.dt
+    }
+    pub fn sound_timer(&self) -> u8 {
+        self.st
+    }
+    // number of return addresses on the stack
+    pub fn sp(&self) -> usize {
+        self.stack.sp()
+    }
+    pub fn memory(&self) -> &[u8] {
+        &self.mem
+    }
+    pub fn set_index(&mut self, addr: u16) {
+        self.ir = addr & self.address_mask();
+    }
+    // changing the state under the game, for debuggers, cheats and scripts
+    pub fn set_register(&mut self, register: usize, value: u8) {
+        self.v[register] = value;
+        self.written |= 1 << register;
+    }
+    pub fn set_pc(&mut self, addr: u16) {
+        self.pc = addr & self.address_mask();
+    }
+    // wraps around the address space like the game's own writes
+    pub fn write_memory(&mut self, addr: u16, bytes: &[u8]) {
+        for (offset, &byte) in bytes.iter().enumerate() {
+            let addr = self.mem_addr(addr, offset as u16);
+            self.mem[addr] = byte;
+            self.invalidate(addr);
+        }
+    }
+    // every address where needle starts, overlapping matches included
+    pub fn find_pattern(&self, needle: &[u8]) -> Vec<u16> {
+        if needle.is_empty() {
+            return Vec::new();
+        }
+        self.mem
+            .windows(needle.len())
+            .enumerate()
+            .filter(|(_, window)| *window == needle)
+            .map(|(addr, _)| addr as u16)
+            .collect()
+    }
+    // the active resolution, which super-chip games can switch at runtime
     pub fn dimensions(&self) -> (usize, usize) {
-        (SCREEN_WIDTH, SCREEN_HEIGHT)
+        if self.hires {
+            (HIRES_WIDTH, HIRES_HEIGHT)
+        } else if self.two_page {
+            (LORES_WIDTH, TWO_PAGE_HEIGHT)
+        } else {
+            (LORES_WIDTH, self.platform.lores_height())
+        }
+    }
+    // the screen at the active resolution, one bit per pixel lit on any plane. rows are packed
+    // most significant bit first, 8 or 16 bytes each
+    pub fn framebuffer(&self) -> Vec<u8> {
+        let (width, height) = self.dimensions();
+        self.screen[..height]
+            .iter()
+            .flat_map(|row| lit(row).to_be_bytes().into_iter().take(width / 8))
+            .collect()
+    }
+    // the screen at the active resolution, row major with one byte per pixel holding the bitmask
+    // of the planes lit there, so 0 is background and 1..=3 are colors
+    pub fn pixels(&self) -> Vec<u8> {
+        let (width, height) = self.dimensions();
+        self.screen[..height]
+            .iter()
+            .flat_map(|row| (0..width).map(|x| planes_at(row, x)))
+            .collect()
+    }
+    // the planes lit at x, y
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        planes_at(&self.screen[y], x)
+    }
+    pub fn screen_hash(&self) -> u64 {
+        // fnv-1a over the pixels, cheap enough to run every frame
+        self.screen_bytes()
+            .iter()
+            .fold(0xcbf2_9ce4_8422_2325, |hash, &pixel| {
+                (hash ^ pixel as u64).wrapping_mul(0x0100_0000_01b3)
+            })
+    }
+    pub fn screen_pixels_since_last_clear(&self) -> usize {
+        self.pixels_drawn_since_clear
+    }
+    // the screen rows that changed since the last call, bit y for row y, so frontends only
+    // redraw those. a new machine starts with all of them
+    pub fn take_dirty_rows(&mut self) -> u64 {
+        core::mem::take(&mut self.dirty_rows)
+    }
+    pub fn pixels_lit(&self) -> usize {
+        self.screen
+            .iter()
+            .map(|row| lit(row).count_ones() as usize)
+            .sum()
+    }
+    pub fn disasm_current_scope(&self, back: usize, forward: usize) -> Vec<ListingEntry> {
+        let pc = self.pc as usize;
+        let start = pc.saturating_sub(2 * back).max(self.platform.start_addr());
+        (start..self.mem.len() - 1)
+            .step_by(2)
+            .take(back + 1 + forward)
+            .map(|addr| {
+                let op = u16::from_be_bytes([self.mem[addr], self.mem[addr + 1]]);
+                ListingEntry {
+                    addr: addr as u16,
+                    op,
+                    text: disasm::disassemble(op),
+                    is_current: addr == pc,
+                    was_executed: addr < pc && self.pc_history.contains(&(addr as u16)),
+                }
+            })
+            .collect()
+    }
+
+    fn check_uninit(&mut self, op: u16) {
+        let (reads, writes) = register_use(op, self.quirks, self.platform);
+        // each register is only reported once, until it's written
+        let tainted = reads & !self.written & !self.warned;
+        for register in (0..REGISTER_COUNT as u8).filter(|r| tainted & (1 << r) != 0) {
+            self.uninit_reads.push(UninitRead {
+                pc: self.pc.wrapping_sub(2) & self.address_mask(),
+                register,
+            });
+        }
+        self.warned |= tainted;
+        self.written |= writes;
+    }
+
+    // halts on the instruction that crashed, so debuggers see where it happened
+    fn crash(&mut self, halt: Halt) {
+        self.halt = Some(halt);
+        self.pc = self.pc.wrapping_sub(2) & self.address_mask();
+    }
+
+    fn emit(&mut self, event: Event) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(?event);
+        if let Some(events) = &mut self.events {
+            events.push(event);
+        }
+    }
+    fn sound_changed(&mut self, tone: bool) {
+        match (tone, self.tone()) {
+            (false, true) => self.emit(Event::SoundStarted),
+            (true, false) => self.emit(Event::SoundStopped),
+            _ => {}
+        }
+    }
+
+    fn unknown_opcode(&mut self, opcode: u16) -> Result<(), Chip8Error> {
+        let unknown = UnknownOpcode {
+            pc: self.pc.wrapping_sub(2) & self.address_mask(),
+            opcode,
+        };
+        match self.opcode_policy {
+            OpcodePolicy::Error => return Err(unknown.into()),
+            OpcodePolicy::Skip => self.unknown_opcodes.push(unknown),
+            OpcodePolicy::Halt => {
+                self.unknown_opcodes.push(unknown);
+                self.crash(Halt::Ended);
+            }
+        }
+        Ok(())
+    }
+
+    // the key ld vx k takes, if any
+    fn next_key(&mut self) -> Option<u8> {
+        match self.key_priority {
+            KeyPriority::Lowest => self.keypad.iter().position(|&k| k).map(|k| k as u8),
+            KeyPriority::MostRecent => self.presses.back().copied(),
+            KeyPriority::PressOrder => self.presses.pop_front(),
+        }
+    }
+
+    fn write_fonts(&mut self) {
+        let font = self.font;
+        let sprites = font.iter().chain(LARGE_FONT_SPRITES);
+        for (offset, &byte) in sprites.enumerate() {
+            let addr = self.mem_addr(self.font_addr, offset as u16);
+            self.mem[addr] = byte;
+            self.invalidate(addr);
+        }
+    }
+    // where the large font sprite for a digit is
+    fn large_font_addr(&self, digit: u8) -> u16 {
+        let offset = FONT_SIZE as u16 + digit as u16 * 10;
+        self.mem_addr(self.font_addr, offset) as u16
+    }
+    // load checked the game fits, but the platform can change afterwards, which cuts it short
+    fn write_rom(&mut self) {
+        let start = self.platform.start_addr();
+        let len = self.rom.len().min(self.mem.len() - start);
+        self.mem[start..(start + len)].copy_from_slice(&self.rom[..len]);
+        self.decoded.clear();
+    }
+    // back to a fresh machine, keeping the game and configuration
+    fn power_cycle(&mut self) {
+        *self = Self {
+            rom: core::mem::take(&mut self.rom),
+            font: self.font,
+            font_addr: self.font_addr,
+            rpl: self.rpl,
+            #[cfg(feature = "std")]
+            profile: self.profile.take(),
+            #[cfg(feature = "jit")]
+            jit: self.jit.take(),
+            events: self.events.take(),
+            color_effect: self.color_effect,
+            plane_priority: self.plane_priority,
+            warn_uninit: self.warn_uninit,
+            sys_policy: core::mem::take(&mut self.sys_policy),
+            opcode_policy: self.opcode_policy,
+            bounds_policy: self.bounds_policy,
+            key_priority: self.key_priority,
+            timer_source: self.timer_source.take(),
+            pre_hooks: core::mem::take(&mut self.pre_hooks),
+            post_hooks: core::mem::take(&mut self.post_hooks),
+            rng: core::mem::replace(&mut self.rng, Box::new(StdRng::seed_from_u64(0))),
+            seed: self.seed,
+            custom_rng: self.custom_rng,
+            rng_tape: core::mem::take(&mut self.rng_tape),
+            boot_garbage: self.boot_garbage,
+            two_page: self.two_page,
+            detect_platform: self.detect_platform,
+            quirks: self.quirks,
+            timing: core::mem::take(&mut self.timing),
+            stack: Stack::with_depth(self.stack.depth()),
+            ..Self::with_memory_size(self.platform, self.memory_size())
+        };
+        self.write_fonts();
+    }
+    // like set_platform, and memory grows to the platform's if it had less, e.g. for xo-chip
+    // games loaded on a 4k machine
+    fn switch_platform(&mut self, platform: Platform) {
+        self.set_platform(platform);
+        if self.mem.len() < platform.memory_size().bytes() {
+            self.mem = vec![0; platform.memory_size().bytes()].into_boxed_slice();
+            self.write_fonts();
+        }
+    }
+    // a replay that ran out can't make up the rest, so the game stops there
+    fn random(&mut self) -> Result<u8, Chip8Error> {
+        match &mut self.rng_tape {
+            RngTape::Off => Ok(self.rng.gen()),
+            RngTape::Recording(draws) => {
+                let draw = self.rng.gen();
+                draws.push(draw);
+                Ok(draw)
+            }
+            RngTape::Replaying(draws) => draws.next().ok_or(Chip8Error::ReplayEnded {
+                pc: self.pc.wrapping_sub(2) & self.address_mask(),
+            }),
+        }
+    }
+
+    fn memory_size(&self) -> MemorySize {
+        match self.mem.len() {
+            0x1000 => MemorySize::Kb4,
+            0x2000 => MemorySize::Kb8,
+            _ => MemorySize::Kb64,
+        }
+    }
+    // i wraps around the address space, like every other address
+    fn address_mask(&self) -> u16 {
+        (self.mem.len() - 1) as u16
+    }
+    // the memory index of base + offset, wrapping around the address space like i does
+    fn mem_addr(&self, base: u16, offset: u16) -> usize {
+        (base.wrapping_add(offset) & self.address_mask()) as usize
+    }
+    // the memory index of i + offset for the game's own reads and writes, past the end of memory
+    // it wraps or fails depending on the bounds policy
+    fn data_addr(&self, offset: u16) -> Result<usize, Chip8Error> {
+        let addr = self.ir as usize + offset as usize;
+        match self.bounds_policy {
+            BoundsPolicy::Wrap => Ok(self.mem_addr(self.ir, offset)),
+            BoundsPolicy::Error if addr < self.mem.len() => Ok(addr),
+            BoundsPolicy::Error => Err(Chip8Error::OutOfBounds {
+                pc: self.pc.wrapping_sub(2) & self.address_mask(),
+                addr,
+            }),
+        }
+    }
+    fn read(&self, offset: u16) -> Result<u8, Chip8Error> {
+        Ok(self.mem[self.data_addr(offset)?])
+    }
+    fn write(&mut self, offset: u16, byte: u8) -> Result<(), Chip8Error> {
+        let addr = self.data_addr(offset)?;
+        self.mem[addr] = byte;
+        self.invalidate(addr);
+        Ok(())
+    }
+
+    // the pixels, then zeros up to SCREEN_SIZE, like save states and the screen hash have
+    // always had them
+    fn screen_bytes(&self) -> Vec<u8> {
+        let mut screen = self.pixels();
+        screen.resize(SCREEN_SIZE, 0);
+        screen
+    }
+    // only the selected planes are cleared
+    fn clear_screen(&mut self) {
+        for row in &mut self.screen {
+            for plane in planes(self.planes) {
+                row[plane] = 0;
+            }
+        }
+        self.dirty_rows = u64::MAX;
+        self.pixels_drawn_since_clear = 0;
+        self.display_changed = true;
+        self.emit(Event::ScreenCleared);
+    }
+    // bxy0 colors zones of 8x4 pixels in vy's color. the low nibbles of vx and v(x + 1) are the
+    // column and row of the first zone, the high nibbles how many more there are to the right
+    // and below. bxyn colors 8 pixels wide and n rows tall from vx, v(x + 1) instead. both wrap
+    // around the edges
+    fn color_area(&mut self, x: u8, y: u8, n: u8) {
+        let color = self.v[y as usize] % FOREGROUND_COLORS;
+        let (horizontal, vertical) = (
+            self.v[x as usize],
+            self.v[(x as usize + 1) % REGISTER_COUNT],
+        );
+        let (columns, rows) = if n == 0 {
+            let zones = |nibbles: u8| {
+                let first = (nibbles & 0xF) as usize;
+                first..=first + (nibbles >> 4) as usize
+            };
+            let rows = zones(vertical);
+            (
+                zones(horizontal),
+                rows.start() * ZONE_HEIGHT..(rows.end() + 1) * ZONE_HEIGHT,
+            )
+        } else {
+            let column = horizontal as usize / 8;
+            (
+                column..=column,
+                vertical as usize..vertical as usize + n as usize,
+            )
+        };
+        for row in rows {
+            let row = row % COLOR_ROWS;
+            for column in columns.clone() {
+                self.colors.foreground[row * COLOR_COLUMNS + column % COLOR_COLUMNS] = color;
+            }
+            self.dirty_rows |= 1 << row;
+        }
+        self.display_changed = true;
+    }
+    // the held state of a key on chip-8x's second keypad
+    fn second_key(&mut self, key: usize) -> Option<&mut bool> {
+        self.second_keypad.get_mut(key.checked_sub(KEY_COUNT)?)
+    }
+    // scrolling moves the selected planes at the active resolution, the uncovered edge is cleared
+    // positive rows scroll down, negative up
+    fn scroll_vertical(&mut self, rows: isize) {
+        let (_, height) = self.dimensions();
+        self.display_changed = true;
+        self.dirty_rows = u64::MAX;
+        let screen = &mut self.screen[..height];
+        let shifted = rows.unsigned_abs().min(height);
+        for plane in planes(self.planes) {
+            if rows > 0 {
+                for y in (0..height).rev() {
+                    screen[y][plane] = y.checked_sub(shifted).map_or(0, |from| screen[from][plane]);
+                }
+            } else {
+                for y in 0..height {
+                    screen[y][plane] = screen.get(y + shifted).map_or(0, |from| from[plane]);
+                }
+            }
+        }
+    }
+    // how far a scroll by pixels goes at the active resolution, super-chip 1.1 scrolls by
+    // hi-res pixels even in lo-res, half as many of the larger ones
+    fn scroll_distance(&self, pixels: isize) -> isize {
+        if self.quirks.half_scroll && !self.hires {
+            pixels / 2
+        } else {
+            pixels
+        }
+    }
+    // positive columns scroll right, negative left
+    fn scroll_sideways(&mut self, columns: isize) {
+        let (width, height) = self.dimensions();
+        let shifted = columns.unsigned_abs().min(width) as u32;
+        self.display_changed = true;
+        self.dirty_rows = u64::MAX;
+        for row in &mut self.screen[..height] {
+            for plane in planes(self.planes) {
+                row[plane] = if columns > 0 {
+                    row[plane].checked_shr(shifted).unwrap_or(0) & visible(width)
+                } else {
+                    row[plane].checked_shl(shifted).unwrap_or(0)
+                };
+            }
+        }
+    }
+
+    // i after fx55/fx65 with the memory quirks
+    fn increment_index(&mut self, x: u8) {
+        if self.quirks.memory {
+            let x = x as u16;
+            let increment = if self.quirks.memory_by_x { x } else { x + 1 };
+            self.ir = self.mem_addr(self.ir, increment) as u16;
+        }
+    }
+
+    // skips the next instruction, which on xo-chip can be the double width f000 nnnn
+    fn skip(&mut self) {
+        let next =
+            [self.mem_addr(self.pc, 0), self.mem_addr(self.pc, 1)].map(|addr| self.mem[addr]);
+        let long = self.platform == Platform::XoChip && next == [0xF0, 0x00];
+        self.pc = self.mem_addr(self.pc, if long { 4 } else { 2 }) as u16;
     }
 
     fn fetch(&mut self) -> u16 {
-        let hi = self.mem[self.pc as usize] as u16;
-        let lo = self.mem[self.pc as usize + 1] as u16;
+        let hi = self.mem[self.mem_addr(self.pc, 0)] as u16;
+        let lo = self.mem[self.mem_addr(self.pc, 1)] as u16;
         let op = (hi << 8) | lo;
-        self.pc += 2;
+        self.pc = self.mem_addr(self.pc, 2) as u16;
         op
     }
-    fn execute(&mut self, op: u16) -> i64 {
-        // split op into 4 nibbles
-        match (
-            (op & 0xF000) >> 12,
-            (op & 0x0F00) >> 8,
-            (op & 0x00F0) >> 4,
-            op & 0x000F,
-        ) {
-            // cls
-            (0, 0, 0xE, 0) => {
-                self.screen.fill(false);
-                109
+    // the compiled blocks depend on the quirks, timing and platform, the decoded costs on the
+    // timing
+    fn recompile(&mut self) {
+        self.decoded.clear();
+        #[cfg(feature = "jit")]
+        if let Some(jit) = &mut self.jit {
+            jit.clear();
+        }
+    }
+    // runs the compiled block at pc if there is one and the interpreter would have run all of
+    // it within the budget, returning how many instructions ran and what they cost
+    #[cfg(feature = "jit")]
+    fn run_compiled(&mut self, budget: i64) -> Option<(u32, i64)> {
+        let watched = self.profile.is_some()
+            || self.warn_uninit
+            || !self.pre_hooks.is_empty()
+            || !self.post_hooks.is_empty();
+        if self.halt.is_some() || self.vblank_wait || watched {
+            return None;
+        }
+        let code = Code {
+            mem: &self.mem,
+            quirks: self.quirks,
+            timing: &self.timing,
+            platform: self.platform,
+            two_page: self.two_page,
+        };
+        let block = self.jit.as_mut()?.block(self.pc, &code)?;
+        // the interpreter starts the last instruction with any budget left
+        if budget <= block.cost - block.last_cost {
+            return None;
+        }
+        let (start, instructions, cost) = (self.pc, block.instructions, block.cost);
+        let next = block.run(&mut self.v, &mut self.ir);
+
+        for n in 0..instructions as u16 {
+            if self.pc_history.len() == PC_HISTORY_SIZE {
+                self.pc_history.pop_front();
+            }
+            self.pc_history
+                .push_back(start.wrapping_add(2 * n) & self.address_mask());
+        }
+        self.ticks += instructions as u64;
+        self.pc = next;
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            pc = %format_args!("{start:03x}"),
+            instructions,
+            "compiled block"
+        );
+        Some((instructions, cost))
+    }
+    // the hooks are moved out while they run, since they borrow the machine. hooks they add are
+    // kept after them
+    fn run_hooks(&mut self, point: HookPoint, pc: u16, decoded: Result<Instruction, u16>) {
+        let mut running = core::mem::take(self.hooks(point));
+        for hook in &mut running {
+            hook(self, pc, decoded);
+        }
+        running.append(self.hooks(point));
+        *self.hooks(point) = running;
+    }
+    fn hooks(&mut self, point: HookPoint) -> &mut Vec<InstructionHook> {
+        match point {
+            HookPoint::Pre => &mut self.pre_hooks,
+            HookPoint::Post => &mut self.post_hooks,
+        }
+    }
+    // decoding only depends on the opcode, so it's done once per address until memory there
+    // changes, along with looking up the cost. pc is wrapped like fetch wraps it
+    fn decode_at(&mut self, pc: u16, op: u16) -> (Result<Instruction, u16>, i64) {
+        if self.decoded.len() != self.mem.len() {
+            self.decoded.clear();
+            self.decoded.resize(self.mem.len(), None);
+        }
+        let addr = self.mem_addr(pc, 0);
+        let timing = &self.timing;
+        *self.decoded[addr].get_or_insert_with(|| (instruction::decode(op), timing.cost(op)))
+    }
+    // drops the instructions a write to the byte at addr changes, the one starting there and
+    // the one before it
+    fn invalidate(&mut self, addr: usize) {
+        if !self.decoded.is_empty() {
+            let before = addr.wrapping_sub(1) & self.address_mask() as usize;
+            self.decoded[addr] = None;
+            self.decoded[before] = None;
+        }
+    }
+    fn execute(&mut self, op: u16, decoded: Result<Instruction, u16>) -> Result<(), Chip8Error> {
+        let instruction = match decoded {
+            Ok(instruction) if self.platform.supports(instruction) => instruction,
+            _ => return self.unknown_opcode(op),
+        };
+        match instruction {
+            // cls, and 0230 clears the screen in hi-res chip-8
+            Clear => self.clear_screen(),
+            Sys(0x230) if self.two_page => self.clear_screen(),
+            // 02a0 cycles the background color on chip-8x
+            Sys(0x2A0) if self.platform == Platform::Chip8X => {
+                self.colors.background = (self.colors.background + 1) % BACKGROUND_COLORS;
+                self.dirty_rows = u64::MAX;
+                self.display_changed = true;
+            }
+            // exit
+            Exit => {
+                self.halt = Some(Halt::Exit);
+            }
+            // low
+            LowRes => {
+                self.hires = false;
+                self.screen.fill(Row::default());
+                self.display_changed = true;
+                self.dirty_rows = u64::MAX;
+                self.pixels_drawn_since_clear = 0;
+            }
+            // high
+            HighRes => {
+                self.hires = true;
+                self.screen.fill(Row::default());
+                self.display_changed = true;
+                self.dirty_rows = u64::MAX;
+                self.pixels_drawn_since_clear = 0;
+            }
+            // scd n
+            ScrollDown(n) => {
+                self.scroll_vertical(self.scroll_distance(n as isize));
+            }
+            // scu n, xo-chip
+            ScrollUp(n) => {
+                self.scroll_vertical(-self.scroll_distance(n as isize));
+            }
+            // scr
+            ScrollRight => {
+                self.scroll_sideways(self.scroll_distance(4));
+            }
+            // scl
+            ScrollLeft => {
+                self.scroll_sideways(-self.scroll_distance(4));
             }
             // ret
-            (0, 0, 0xE, 0xE) => {
-                self.pc = self.stack.pop().unwrap();
-                105
+            Return => {
+                self.pc = self.stack.pop()?;
             }
-            // jp
-            (1, ..) => {
-                self.pc = op & 0x0FFF;
-                105
+            // sys nnn, anything in 0nnn that isn't one of the instructions above
+            Sys(addr) => match &mut self.sys_policy {
+                SysPolicy::Callback(callback) => {
+                    callback(addr, &mut self.v, &mut self.mem);
+                    // it could have written anywhere
+                    self.decoded.clear();
+                }
+                policy => {
+                    if matches!(policy, SysPolicy::Halt) {
+                        self.halt = Some(Halt::SysCall);
+                    }
+                    self.sys_calls.push(SysCall {
+                        pc: self.pc.wrapping_sub(2) & self.address_mask(),
+                        addr,
+                    });
+                }
+            },
+            // jp, the jump into the hi-res chip-8 interpreter patch skips straight past it
+            Jump(addr) => {
+                let here = self.pc.wrapping_sub(2) & self.address_mask();
+                let boot = self.pc as usize == self.platform.start_addr() + 2;
+                self.pc = if self.two_page && boot && addr == 0x260 {
+                    TWO_PAGE_ENTRY
+                } else {
+                    addr
+                };
+                // nothing can ever break out of a jump to itself, so stop spinning on it
+                if self.pc == here {
+                    self.halt = Some(Halt::Ended);
+                }
             }
             // call
-            (2, ..) => {
-                self.stack.push(self.pc);
-                self.pc = op & 0x0FFF;
-                105
+            Call(addr) => {
+                self.stack.push(self.pc)?;
+                self.pc = addr;
             }
             // se vx nn
-            (3, x, ..) => {
-                if self.v[x as usize] == nn(op) {
-                    self.pc += 2
+            SkipEqual(x, nn) => {
+                if self.v[x as usize] == nn {
+                    self.skip();
                 }
-                55
             }
             // sne vx nn
-            (4, x, ..) => {
-                if self.v[x as usize] != nn(op) {
-                    self.pc += 2
+            SkipNotEqual(x, nn) => {
+                if self.v[x as usize] != nn {
+                    self.skip();
                 }
-                55
             }
             // se vx vy
-            (5, x, y, _) => {
+            SkipEqualRegisters(x, y) => {
                 if self.v[x as usize] == self.v[y as usize] {
-                    self.pc += 2
+                    self.skip();
+                }
+            }
+            // nadd vx vy, chip-8x adds each nibble on its own, wrapping around at 8 like the zone
+            // coordinates bxy0 takes
+            AddNibbles(x, y) => {
+                let sum = (self.v[x as usize] & 0x77) + (self.v[y as usize] & 0x77);
+                self.v[x as usize] = sum & 0x77;
+            }
+            // save vx - vy, xo-chip leaves i where it is
+            SaveRange(x, y) => {
+                for (offset, register) in register_range(x, y).enumerate() {
+                    self.write(offset as u16, self.v[register])?;
+                }
+            }
+            // load vx - vy, xo-chip
+            LoadRange(x, y) => {
+                for (offset, register) in register_range(x, y).enumerate() {
+                    self.v[register] = self.read(offset as u16)?;
                 }
-                73
             }
             // ld vx nn
-            (6, x, ..) => {
-                self.v[x as usize] = nn(op);
-                27
+            Load(x, nn) => {
+                self.v[x as usize] = nn;
             }
             // add vx byte
-            (7, x, ..) => {
-                self.v[x as usize] = self.v[x as usize].wrapping_add(nn(op));
-                45
-            }
-            // set vx vy
-            (8, x, y, 0) => {
-                self.v[x as usize] = self.v[y as usize];
-                200
-            }
-            // or vx vy
-            (8, x, y, 1) => {
-                self.v[x as usize] |= self.v[y as usize];
-                self.v[0xF] = 0;
-                200
-            }
-            // and vx vy
-            (8, x, y, 2) => {
-                self.v[x as usize] &= self.v[y as usize];
-                self.v[0xF] = 0;
-                200
-            }
-            // xor vx vy
-            (8, x, y, 3) => {
-                self.v[x as usize] ^= self.v[y as usize];
-                self.v[0xF] = 0;
-                200
-            }
-            // add vx vy
-            (8, x, y, 4) => {
-                let (res, overflow) = self.v[x as usize].overflowing_add(self.v[y as usize]);
-                self.v[x as usize] = res;
-                self.v[0xF] = overflow as u8;
-                200
+            Add(x, nn) => {
+                self.v[x as usize] = self.v[x as usize].wrapping_add(nn);
             }
-            // sub vx vy
-            (8, x, y, 5) => {
-                let (res, borrow) = self.v[x as usize].overflowing_sub(self.v[y as usize]);
+            // 8xyN arithmetic, logic and shifts
+            Alu(x, y, op) => {
+                let (res, flag) = alu(op, self.v[x as usize], self.v[y as usize], self.quirks);
+                // vf is written last so the flag wins when x is 0xF
                 self.v[x as usize] = res;
-                self.v[0xF] = !borrow as u8;
-                200
-            }
-            // shr vx
-            (8, x, y, 6) => {
-                self.v[x as usize] = self.v[y as usize];
-                let lsb = self.v[x as usize] & 1;
-                self.v[x as usize] >>= 1;
-                self.v[0xF] = lsb;
-                200
-            }
-            // sub vx vy
-            (8, x, y, 7) => {
-                let (res, borrow) = self.v[y as usize].overflowing_sub(self.v[x as usize]);
-                self.v[x as usize] = res;
-                self.v[0xF] = !borrow as u8;
-                200
-            }
-            // shl vx
-            (8, x, y, 0xE) => {
-                self.v[x as usize] = self.v[y as usize];
-                let msb = (self.v[x as usize] >> 7) & 1;
-                self.v[x as usize] <<= 1;
-                self.v[0xF] = msb;
-                200
+                if let Some(flag) = flag {
+                    self.v[0xF] = flag;
+                }
             }
             // sne vx, vy
-            (9, x, y, 0) => {
+            SkipNotEqualRegisters(x, y) => {
                 if self.v[x as usize] != self.v[y as usize] {
-                    self.pc += 2
+                    self.skip();
                 }
-                73
             }
             // ld i nnn
-            (0xA, ..) => {
-                self.ir = op & 0x0FFF;
-                55
+            LoadIndex(addr) => {
+                self.ir = addr;
             }
-            // jp v0 nnn
-            (0xB, ..) => {
-                self.pc = self.v[0] as u16 + nnn(op);
-                105
+            // bxyn colors the screen on chip-8x, see color_area
+            JumpOffset(x, addr) if self.platform == Platform::Chip8X => {
+                self.color_area(x, (addr >> 4) as u8 & 0xF, addr as u8 & 0xF);
+            }
+            // jp v0 nnn, or jp vx xnn with the jumping quirk, wrapping around memory
+            JumpOffset(x, addr) => {
+                let offset = if self.quirks.jumping { x } else { 0 };
+                self.pc = self.mem_addr(addr, self.v[offset as usize] as u16) as u16;
             }
             // rnd vx nn
-            (0xC, x, ..) => {
-                self.v[x as usize] = rand::random::<u8>() & nn(op);
-                164
-            }
-            // drw vx vy n
-            (0xD, x, y, n) => {
-                let x_coord = (self.v[x as usize] % SCREEN_WIDTH as u8) as u16;
-                let y_coord = (self.v[y as usize] % SCREEN_HEIGHT as u8) as u16;
-
-                let mut flipped = false;
-                for y_line in 0..n {
-                    let addr = self.ir + y_line;
-                    let pixels = self.mem[addr as usize];
-                    for x_line in 0..8 {
-                        if (pixels & (0b1000_0000 >> x_line)) != 0 {
-                            let x = (x_coord + x_line) as usize;
-                            let y = (y_coord + y_line) as usize;
-                            let idx = x + SCREEN_WIDTH * y;
-                            if let Some(pixel) = self.screen.get_mut(idx) {
-                                flipped |= *pixel;
-                                *pixel ^= true;
-                            }
+            Random(x, nn) => {
+                self.v[x as usize] = self.random()? & nn;
+            }
+            // drw vx vy n, dxy0 draws a 16x16 sprite of two bytes per row in hi-res and 8x16 in
+            // lo-res
+            Draw(x, y, n) => {
+                let (width, height) = self.dimensions();
+                let x_coord = self.v[x as usize] as usize % width;
+                let y_coord = self.v[y as usize] as usize % height;
+                let (sprite_width, rows) = match n {
+                    0 if self.hires => (16, 16),
+                    0 => (8, 16),
+                    n => (8, n as u16),
+                };
+                let row_bytes = sprite_width / 8;
+
+                // each selected plane draws its own sprite, stored one after the other
+                let mut flipped_rows = 0;
+                let mut clipped_rows = 0;
+                for (sprite, plane) in planes(self.planes).enumerate() {
+                    let sprite_addr = sprite as u16 * rows * row_bytes;
+                    for y_line in 0..rows {
+                        let pixels = (0..row_bytes).try_fold(0u16, |pixels, byte| {
+                            let offset = sprite_addr + y_line * row_bytes + byte;
+                            self.read(offset).map(|byte| (pixels << 8) | byte as u16)
+                        })?;
+                        let y = y_coord + y_line as usize;
+                        if self.quirks.clipping && y >= height {
+                            clipped_rows += 1;
+                            continue;
+                        }
+                        // the whole row at once, collisions are the bits already lit
+                        let bits = sprite_row(pixels, sprite_width, x_coord, width, self.quirks);
+                        let row = &mut self.screen[y % height];
+                        self.pixels_drawn_since_clear += (bits & !lit(row)).count_ones() as usize;
+                        flipped_rows += (row[plane] & bits != 0) as u8;
+                        row[plane] ^= bits;
+                        if bits != 0 {
+                            self.display_changed = true;
+                            self.dirty_rows |= 1 << (y % height);
                         }
                     }
                 }
-                self.v[0xF] = flipped as u8;
-
-                22734
+                // super-chip 1.1 counts the rows that collided or were clipped at the bottom in
+                // hi-res, everything else only reports whether anything collided
+                self.v[0xF] = if self.hires && self.platform == Platform::SuperChip {
+                    flipped_rows + clipped_rows
+                } else {
+                    (flipped_rows > 0) as u8
+                };
+                self.collided |= flipped_rows > 0;
+                self.vblank_wait = self.quirks.display_wait;
+                self.emit(Event::SpriteDrawn {
+                    x: x_coord as u8,
+                    y: y_coord as u8,
+                    height: rows as u8,
+                });
             }
-            // skp vx
-            (0xE, x, 9, 0xE) => {
-                if self.keypad[self.v[x as usize] as usize] {
-                    self.pc += 2;
+            // skp vx, only the low nibble picks the key like on the vip
+            SkipKey(x) => {
+                if self.keypad[(self.v[x as usize] & 0xF) as usize] {
+                    self.skip();
                 }
-                73
             }
             // sknp vx
-            (0xE, x, 0xA, 1) => {
-                if !self.keypad[self.v[x as usize] as usize] {
-                    self.pc += 2;
+            SkipNotKey(x) => {
+                if !self.keypad[(self.v[x as usize] & 0xF) as usize] {
+                    self.skip();
+                }
+            }
+            // skp2 vx, chip-8x's second keypad
+            SkipKey2(x) => {
+                if self.second_keypad[(self.v[x as usize] & 0xF) as usize] {
+                    self.skip();
                 }
-                73
+            }
+            // sknp2 vx
+            SkipNotKey2(x) => {
+                if !self.second_keypad[(self.v[x as usize] & 0xF) as usize] {
+                    self.skip();
+                }
+            }
+            // ld i long nnnn, xo-chip takes the address from the next word
+            LoadIndexLong => {
+                self.ir = self.fetch();
+            }
+            // audio, xo-chip
+            Audio => {
+                let mut pattern = [0; AUDIO_PATTERN_SIZE];
+                for (offset, sample) in pattern.iter_mut().enumerate() {
+                    *sample = self.read(offset as u16)?;
+                }
+                self.audio_pattern = Some(pattern);
+            }
+            // plane n, xo-chip
+            Plane(n) => {
+                self.planes = n & ((1 << PLANE_COUNT) - 1);
             }
             // ld vx dt
-            (0xF, x, 0, 7) => {
+            LoadDelay(x) => {
                 self.v[x as usize] = self.dt;
-                45
-            }
-            // ld vx k
-            (0xF, x, 0, 0xA) => {
-                let mut pressed = false;
-                #[allow(clippy::needless_range_loop)]
-                for i in 0..self.keypad.len() {
-                    if self.keypad[i] {
-                        pressed = true;
-                        self.v[x as usize] = i as u8;
-                        // wait for release
-
-                        break;
-                    }
+            }
+            // ld vx k, waits for a key to be pressed and then released like the vip did, so a
+            // held key doesn't complete one wait after another
+            WaitKey(x) => match self.key_wait {
+                Some(key) if self.releases & (1 << key) != 0 => {
+                    self.v[x as usize] = key;
+                    self.key_wait = None;
+                    self.waiting_for_key = false;
                 }
-                if !pressed {
-                    self.pc -= 2;
+                _ => {
+                    if !self.waiting_for_key {
+                        self.waiting_for_key = true;
+                        self.emit(Event::WaitingForKey);
+                        // a key tapped long before the wait would complete it right away
+                        let keypad = self.keypad;
+                        self.presses.retain(|&pressed| keypad[pressed as usize]);
+                    }
+                    if self.key_wait.is_none() {
+                        self.key_wait = self.next_key();
+                        // a key tapped between two ticks is already released again
+                        self.releases = match self.key_wait {
+                            Some(key) if !self.keypad[key as usize] => 1 << key,
+                            _ => 0,
+                        };
+                    }
+                    self.pc = self.pc.wrapping_sub(2) & self.address_mask();
                 }
-                100
-            }
+            },
             // ld dt vx
-            (0xF, x, 1, 5) => {
+            SetDelay(x) => {
                 self.dt = self.v[x as usize];
-                45
             }
             // ld st vx
-            (0xF, x, 1, 8) => {
+            SetSound(x) => {
+                let tone = self.tone();
                 self.st = self.v[x as usize];
-                45
+                self.sound_changed(tone);
             }
             // add ir vx
-            (0xF, x, 1, 0xE) => {
-                self.ir = self.ir.wrapping_add(self.v[x as usize] as u16);
-                86
+            AddIndex(x) => {
+                self.ir = self.mem_addr(self.ir, self.v[x as usize] as u16) as u16;
             }
             // ld f vx
-            (0xF, x, 2, 9) => {
-                self.ir = self.v[x as usize] as u16 * 5;
-                91
+            Font(x) => {
+                self.ir = self.mem_addr(self.font_addr, self.v[x as usize] as u16 * 5) as u16;
+            }
+            // ld hf vx, super-chip only has large digits
+            LargeFont(x) => {
+                self.ir = self.large_font_addr(self.v[x as usize] & 0xF);
+            }
+            // pitch vx, xo-chip
+            Pitch(x) => {
+                self.pitch = self.v[x as usize];
             }
             // ld b cx
-            (0xF, x, 3, 3) => {
+            Bcd(x) => {
                 let vx = self.v[x as usize];
-                self.mem[self.ir as usize] = (vx / 100) % 10;
-                self.mem[self.ir as usize + 1] = (vx / 10) % 10;
-                self.mem[self.ir as usize + 2] = vx % 10;
-                927
+                for (offset, digit) in [vx / 100, (vx / 10) % 10, vx % 10].into_iter().enumerate() {
+                    self.write(offset as u16, digit)?;
+                }
             }
             // ld ir vx
-            (0xF, x, 5, 5) => {
+            Store(x) => {
                 for offset in 0..=(x as usize) {
-                    self.mem[self.ir as usize + offset] = self.v[offset];
+                    self.write(offset as u16, self.v[offset])?;
                 }
-                self.ir += 1;
-                605
+                self.increment_index(x);
             }
             // ld vx ir
-            (0xF, x, 6, 5) => {
+            Restore(x) => {
                 for offset in 0..=(x as usize) {
-                    self.v[offset] = self.mem[self.ir as usize + offset];
+                    self.v[offset] = self.read(offset as u16)?;
                 }
-                self.ir += 1;
-                605
+                self.increment_index(x);
             }
-            _ => todo!("unimplemented opcode: {op:04x}"),
+            // ld r vx
+            StoreFlags(x) => {
+                self.rpl[..=x as usize].copy_from_slice(&self.v[..=x as usize]);
+            }
+            // ld vx r
+            RestoreFlags(x) => {
+                self.v[..=x as usize].copy_from_slice(&self.rpl[..=x as usize]);
+            }
+        }
+        Ok(())
+    }
+}
+impl Default for Chip8 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// computes the result and flag of an 8xyN op, a flag of None leaves vf untouched.
+//
+// op    result                     vf
+// 8xy0  vy                         untouched
+// 8xy1  vx | vy                    0 if vf_reset, else untouched
+// 8xy2  vx & vy                    0 if vf_reset, else untouched
+// 8xy3  vx ^ vy                    0 if vf_reset, else untouched
+// 8xy4  vx + vy                    1 on carry, else 0
+// 8xy5  vx - vy                    0 on borrow, else 1
+// 8xy6  vy >> 1, vx if shifting    bit shifted out
+// 8xy7  vy - vx                    0 on borrow, else 1
+// 8xyE  vy << 1, vx if shifting    bit shifted out
+fn alu(op: AluOp, vx: u8, vy: u8, quirks: Quirks) -> (u8, Option<u8>) {
+    let reset = quirks.vf_reset.then_some(0);
+    let shifted = if quirks.shifting { vx } else { vy };
+    match op {
+        AluOp::Load => (vy, None),
+        AluOp::Or => (vx | vy, reset),
+        AluOp::And => (vx & vy, reset),
+        AluOp::Xor => (vx ^ vy, reset),
+        AluOp::Add => {
+            let (res, carry) = vx.overflowing_add(vy);
+            (res, Some(carry as u8))
+        }
+        AluOp::Sub => {
+            let (res, borrow) = vx.overflowing_sub(vy);
+            (res, Some(!borrow as u8))
+        }
+        AluOp::Shr => (shifted >> 1, Some(shifted & 1)),
+        AluOp::SubN => {
+            let (res, borrow) = vy.overflowing_sub(vx);
+            (res, Some(!borrow as u8))
+        }
+        AluOp::Shl => (shifted << 1, Some(shifted >> 7)),
+    }
+}
+
+// the planes of a screen row, the leftmost pixel in the most significant bit
+type Row = [u128; PLANE_COUNT as usize];
+
+// the planes in a bitmask of them
+fn planes(mask: u8) -> impl Iterator<Item = usize> {
+    (0..PLANE_COUNT as usize).filter(move |plane| mask & 1 << plane != 0)
+}
+// the bit of the pixel at x in a row
+fn column(x: usize) -> u128 {
+    1 << (127 - x)
+}
+// the pixels lit on any plane
+fn lit(row: &Row) -> u128 {
+    row.iter().fold(0, |lit, plane| lit | plane)
+}
+// the columns of a row width pixels wide
+fn visible(width: usize) -> u128 {
+    u128::MAX << (128 - width)
+}
+// the bitmask of the planes lit at x
+fn planes_at(row: &Row, x: usize) -> u8 {
+    (0..PLANE_COUNT as usize).fold(0, |pixel, plane| {
+        pixel | ((row[plane] & column(x) != 0) as u8) << plane
+    })
+}
+// a row of a sprite moved to x on a screen row, past the right edge it's clipped or wraps
+// around to the left
+fn sprite_row(pixels: u16, sprite_width: u16, x: usize, width: usize, quirks: Quirks) -> u128 {
+    let row = (pixels as u128) << (128 - sprite_width);
+    let shifted = row >> x;
+    if quirks.clipping {
+        shifted & visible(width)
+    } else if width == 128 {
+        row.rotate_right(x as u32)
+    } else {
+        (shifted | shifted << width) & visible(width)
+    }
+}
+
+// bitmasks of the v registers an op reads and writes
+fn register_use(op: u16, quirks: Quirks, platform: Platform) -> (u16, u16) {
+    let Ok(instruction) = instruction::decode(op) else {
+        return (0, 0);
+    };
+    let bit = |register: u8| 1 << register;
+    let up_to = |register: u8| u16::MAX >> (0xF - register); // v0..=vx
+    let range = |x: u8, y: u8| register_range(x, y).fold(0, |mask, register| mask | 1 << register);
+    let vf = bit(0xF);
+    match instruction {
+        SkipEqual(x, _) | SkipNotEqual(x, _) => (bit(x), 0),
+        SkipEqualRegisters(x, y) | SkipNotEqualRegisters(x, y) => (bit(x) | bit(y), 0),
+        AddNibbles(x, y) => (bit(x) | bit(y), bit(x)),
+        Load(x, _) => (0, bit(x)),
+        Add(x, _) => (bit(x), bit(x)),
+        Alu(x, y, AluOp::Load) => (bit(y), bit(x)),
+        Alu(x, y, AluOp::Shr | AluOp::Shl) => {
+            let shifted = if quirks.shifting { x } else { y };
+            (bit(shifted), bit(x) | vf)
+        }
+        Alu(x, y, _) => (bit(x) | bit(y), bit(x) | vf),
+        JumpOffset(x, nnn) if platform == Platform::Chip8X => {
+            let y = (nnn >> 4) as u8 & 0xF;
+            (bit(x) | bit((x + 1) % REGISTER_COUNT as u8) | bit(y), 0)
         }
+        JumpOffset(x, _) => (bit(if quirks.jumping { x } else { 0 }), 0),
+        Random(x, _) => (0, bit(x)),
+        Draw(x, y, _) => (bit(x) | bit(y), vf),
+        SkipKey(x) | SkipNotKey(x) | SkipKey2(x) | SkipNotKey2(x) => (bit(x), 0),
+        LoadDelay(x) | WaitKey(x) => (0, bit(x)),
+        SetDelay(x) | SetSound(x) | AddIndex(x) | Font(x) | LargeFont(x) | Pitch(x) | Bcd(x) => {
+            (bit(x), 0)
+        }
+        Store(x) | StoreFlags(x) => (up_to(x), 0),
+        Restore(x) | RestoreFlags(x) => (0, up_to(x)),
+        SaveRange(x, y) => (range(x, y), 0),
+        LoadRange(x, y) => (0, range(x, y)),
+        _ => (0, 0),
     }
 }
 
-const fn nn(op: u16) -> u8 {
-    (op & 0x00FF) as u8
+// vx to vy, backwards when y comes before x
+fn register_range(x: u8, y: u8) -> impl Iterator<Item = usize> {
+    let (x, y) = (x as usize, y as usize);
+    (0..=x.abs_diff(y)).map(move |i| if x <= y { x + i } else { x - i })
+}
+
+fn entropy_rng() -> Box<dyn RngCore + Send> {
+    #[cfg(feature = "std")]
+    return Box::new(StdRng::from_entropy());
+    // no entropy source without std, games get the same numbers unless given a seed or rng
+    #[cfg(not(feature = "std"))]
+    Box::new(StdRng::seed_from_u64(0))
 }
-const fn nnn(op: u16) -> u16 {
-    op & 0x0FFF
+
+// serde only handles arrays up to 32 elements, longer ones go through a slice
+#[cfg(feature = "serde")]
+mod big_array {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer, T: Serialize, const N: usize>(
+        array: &[T; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        array.as_slice().serialize(serializer)
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>, T: Deserialize<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<[T; N], D::Error> {
+        let bytes = Vec::<T>::deserialize(deserializer)?;
+        let len = bytes.len();
+        bytes
+            .try_into()
+            .map_err(|_| D::Error::invalid_length(len, &"an array of the same size"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    // runs a game for a number of ticks, stopping at the first error
+    fn run(
+        rom: &[u8],
+        setup: impl FnOnce(&mut Chip8),
+        ticks: usize,
+    ) -> (Chip8, Result<(), Chip8Error>) {
+        let mut chip8 = Chip8::new();
+        chip8.load(rom).unwrap();
+        setup(&mut chip8);
+        let result = (0..ticks).try_for_each(|_| chip8.tick().map(|_| ()));
+        (chip8, result)
+    }
+
+    fn hashes(game: &[u8], ticks: usize) -> Vec<u64> {
+        let mut chip8 = Chip8::new();
+        chip8.load(game).unwrap();
+        (0..ticks)
+            .map(|_| {
+                chip8.tick().unwrap();
+                chip8.screen_hash()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_game_hashes_the_same_screens() {
+        let game = include_bytes!("../test/2-ibm-logo.ch8");
+        let first = hashes(game, 40);
+        assert_eq!(first, hashes(game, 40));
+        // the logo is drawn piece by piece, so the checksum has to change along the way
+        assert!(first.windows(2).any(|pair| pair[0] != pair[1]));
+    }
+
+    #[test]
+    fn set_index_points_drw_at_a_font_digit() {
+        // drw v0, v1, 5 at the top left corner
+        let (mut chip8, _) = run(&[0xD0, 0x15], |c| c.set_index(0), 0);
+        chip8.tick().unwrap();
+        let pixels = chip8.pixels();
+        let row = |y: usize| &pixels[y * 64..y * 64 + 4];
+        // the 0 digit is f0 90 90 90 f0
+        assert_eq!(row(0), [1, 1, 1, 1]);
+        assert_eq!(row(1), [1, 0, 0, 1]);
+        assert_eq!(row(3), [1, 0, 0, 1]);
+        assert_eq!(row(4), [1, 1, 1, 1]);
+        assert_eq!(chip8.pixels_lit(), 14);
+        chip8.set_index(0xFFFF);
+        assert_eq!(chip8.index(), 0x0FFF);
+    }
+
+    #[test]
+    fn find_pattern_finds_every_match() {
+        let mut chip8 = Chip8::new();
+        chip8.mem[0x300..0x304].copy_from_slice(&[0xDE, 0xAD, 0xBE, 0xEF]);
+        chip8.mem[0x400..0x404].copy_from_slice(&[0xDE, 0xAD, 0xBE, 0xEF]);
+        assert_eq!(
+            chip8.find_pattern(&[0xDE, 0xAD, 0xBE, 0xEF]),
+            vec![0x300, 0x400]
+        );
+        // overlapping matches are all found
+        chip8.mem[0x500..0x503].copy_from_slice(&[0xAB, 0xAB, 0xAB]);
+        assert_eq!(chip8.find_pattern(&[0xAB, 0xAB]), vec![0x500, 0x501]);
+        assert_eq!(chip8.find_pattern(&[]), Vec::<u16>::new());
+    }
+
+    #[test]
+    fn index_add_wraps_at_the_platform_address_space() {
+        // v0 = 4, i = 0xffe, then i += v0
+        let rom = [0x60, 0x04, 0xAF, 0xFE, 0xF0, 0x1E];
+        let (chip8, _) = run(&rom, |_| {}, 3);
+        assert_eq!(chip8.index(), 0x002);
+        let mut chip8 = Chip8::with_platform(Platform::XoChip);
+        chip8.load(&rom).unwrap();
+        (0..3).for_each(|_| _ = chip8.tick().unwrap());
+        assert_eq!(chip8.index(), 0x1002);
+    }
+
+    #[test]
+    fn drw_reads_rows_across_the_end_of_memory_like_mem_addr() {
+        // i = 0xffe, drw v0, v1, 5 reads 0xffe, 0xfff, then 0x000 to 0x002
+        let (mut chip8, _) = run(&[0xAF, 0xFE, 0xD0, 0x15], |_| {}, 1);
+        chip8.write_memory(0xFFE, &[0x80, 0x40, 0x20, 0x10, 0x08]);
+        chip8.tick().unwrap();
+        let framebuffer = chip8.framebuffer();
+        for row in 0..5 {
+            let addr = chip8.mem_addr(0xFFE, row);
+            assert_eq!(framebuffer[row as usize * 8], chip8.mem[addr]);
+        }
+        assert_eq!(chip8.mem_addr(0xFFE, 4), 0x002);
+    }
+
+    #[test]
+    fn sprites_clip_or_wrap_at_the_screen_edges() {
+        let draw_in_corner = |clipping| {
+            let quirks = Quirks {
+                clipping,
+                ..Quirks::default()
+            };
+            // v0 = 62, v1 = 30, i = 0, then drw v0, v1, 5 draws the 0 digit over the corner
+            let rom = [0x60, 0x3E, 0x61, 0x1E, 0xA0, 0x00, 0xD0, 0x15];
+            run(&rom, |c| c.set_quirks(quirks), 4).0
+        };
+        let clipped = draw_in_corner(true);
+        // only f0 and 90 fit, two columns of each
+        assert_eq!(clipped.pixels_lit(), 3);
+        assert_eq!((clipped.pixel(62, 30), clipped.pixel(0, 30)), (1, 0));
+        let wrapped = draw_in_corner(false);
+        assert_eq!(wrapped.pixels_lit(), 14);
+        // the right half wraps to the left edge and the bottom rows to the top
+        assert_eq!((wrapped.pixel(62, 30), wrapped.pixel(1, 30)), (1, 1));
+        assert_eq!((wrapped.pixel(62, 2), wrapped.pixel(1, 2)), (1, 1));
+    }
+
+    #[test]
+    fn pc_past_the_end_of_memory_runs_wrapped() {
+        // v0 = 7 at 0xffe, run from the raw address past it
+        let (mut chip8, _) = run(&[], |_| {}, 0);
+        chip8.write_memory(0xFFE, &[0x60, 0x07]);
+        chip8.pc = 0x1FFE;
+        chip8.tick().unwrap();
+        assert_eq!(chip8.v[0], 7);
+        assert_eq!(chip8.pc & chip8.address_mask(), 0x000);
+    }
+
+    #[test]
+    fn jump_offset_wraps_around_memory() {
+        // v0 = 0xff, then jp v0, 0xfff
+        let (chip8, result) = run(&[0x60, 0xFF, 0xBF, 0xFF], |_| {}, 2);
+        assert_eq!(result, Ok(()));
+        assert_eq!(chip8.pc, 0x0FE);
+    }
+
+    #[test]
+    fn key_skips_use_the_low_nibble_of_vx() {
+        // v0 = 0x20, then skp v0 tests key 0
+        let rom = [0x60, 0x20, 0xE0, 0x9E];
+        let (chip8, result) = run(&rom, |c| c.key_down(0), 2);
+        assert_eq!((result, chip8.pc), (Ok(()), 0x206));
+        let (chip8, result) = run(&rom, |_| {}, 2);
+        assert_eq!((result, chip8.pc), (Ok(()), 0x204));
+    }
+
+    #[test]
+    fn alu_follows_the_behavior_matrix() {
+        let quirks = |vf_reset, shifting| Quirks {
+            vf_reset,
+            shifting,
+            ..Quirks::default()
+        };
+        for vf_reset in [false, true] {
+            for shifting in [false, true] {
+                let q = quirks(vf_reset, shifting);
+                let reset = vf_reset.then_some(0);
+                assert_eq!(alu(AluOp::Load, 0x0C, 0x0A, q), (0x0A, None));
+                assert_eq!(alu(AluOp::Or, 0x0C, 0x0A, q), (0x0E, reset));
+                assert_eq!(alu(AluOp::And, 0x0C, 0x0A, q), (0x08, reset));
+                assert_eq!(alu(AluOp::Xor, 0x0C, 0x0A, q), (0x06, reset));
+                assert_eq!(alu(AluOp::Add, 0xF0, 0x0F, q), (0xFF, Some(0)));
+                assert_eq!(alu(AluOp::Add, 0xF0, 0x20, q), (0x10, Some(1)));
+                assert_eq!(alu(AluOp::Sub, 0x20, 0x10, q), (0x10, Some(1)));
+                assert_eq!(alu(AluOp::Sub, 0x10, 0x20, q), (0xF0, Some(0)));
+                assert_eq!(alu(AluOp::SubN, 0x10, 0x20, q), (0x10, Some(1)));
+                assert_eq!(alu(AluOp::SubN, 0x20, 0x10, q), (0xF0, Some(0)));
+                // vx is 0b1000_0001 and vy 0b0100_0010, which one shifts depends on the quirk
+                let (vx, vy) = (0x81, 0x42);
+                let shifted = if shifting { vx } else { vy };
+                assert_eq!(
+                    alu(AluOp::Shr, vx, vy, q),
+                    (shifted >> 1, Some(shifted & 1))
+                );
+                assert_eq!(
+                    alu(AluOp::Shl, vx, vy, q),
+                    (shifted << 1, Some(shifted >> 7))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn flag_wins_over_the_result_in_vf() {
+        // vf = 0xff, v1 = 0x02, then vf += v1 carries
+        let (chip8, _) = run(&[0x6F, 0xFF, 0x61, 0x02, 0x8F, 0x14], |_| {}, 3);
+        assert_eq!(chip8.v[0xF], 1);
+        // vf = 0x10, v1 = 0x20, then vf -= v1 borrows
+        let (chip8, _) = run(&[0x6F, 0x10, 0x61, 0x20, 0x8F, 0x15], |_| {}, 3);
+        assert_eq!(chip8.v[0xF], 0);
+    }
+
+    #[test]
+    fn profile_records_time_for_the_executed_opcodes() {
+        // i = 0, drw v0, v0, 5 and jump back to it
+        let rom = [0xA0, 0x00, 0xD0, 0x05, 0x12, 0x02];
+        let setup = |c: &mut Chip8| {
+            // without waiting for vblank after each draw, so ticks alone get back to the jump
+            c.set_quirks(Quirks {
+                display_wait: false,
+                ..Quirks::default()
+            });
+            c.enable_opcode_profile();
+        };
+        let (chip8, _) = run(&rom, setup, 50);
+        let profile = chip8.opcode_profile().unwrap();
+        assert!(profile.time("drw vx, vy, n") > Duration::ZERO);
+        assert!(profile.time("jp nnn") > Duration::ZERO);
+        assert_eq!(profile.time("cls"), Duration::ZERO);
+        // a header and a row for each of the three instructions
+        assert_eq!(profile.to_string().lines().count(), 4);
+    }
+
+    #[test]
+    fn reading_an_unwritten_register_warns() {
+        // v0 += v3 before v3 was written
+        let (mut chip8, _) = run(&[0x80, 0x34], |c| c.warn_uninit(true), 1);
+        let reads = chip8.take_uninit_reads();
+        assert!(reads.contains(&UninitRead {
+            pc: 0x200,
+            register: 3
+        }));
+        // v3 = 1, v0 = 0, then v0 += v3
+        let rom = [0x63, 0x01, 0x60, 0x00, 0x80, 0x34];
+        let (mut chip8, _) = run(&rom, |c| c.warn_uninit(true), 3);
+        assert_eq!(chip8.take_uninit_reads(), vec![]);
+        let (mut chip8, _) = run(&[0x80, 0x34], |_| {}, 1);
+        assert_eq!(chip8.take_uninit_reads(), vec![]);
+    }
+
+    #[test]
+    fn replaying_recorded_draws_repeats_the_game() {
+        let rom = [0xC0, 0xFF, 0xC1, 0x0F, 0xC2, 0xFF];
+        let (recorded, _) = run(&rom, |c| c.record_rng(), 3);
+        assert_eq!(recorded.recorded_rng().len(), 3);
+        let draws = recorded.recorded_rng().to_vec();
+        let (replayed, result) = run(&rom, |c| c.replay_rng(draws), 3);
+        assert_eq!(result, Ok(()));
+        assert_eq!(replayed.v, recorded.v);
+    }
+
+    #[test]
+    fn replay_running_out_is_an_error() {
+        let (_, result) = run(&[0xC0, 0xFF, 0xC1, 0xFF], |c| c.replay_rng(vec![7]), 2);
+        assert_eq!(result, Err(Chip8Error::ReplayEnded { pc: 0x202 }));
+    }
+
+    #[test]
+    fn seeded_warm_reset_keeps_the_random_sequence() {
+        let rom = [0xC0, 0xFF];
+        let draw = |c: &mut Chip8| {
+            c.tick().unwrap();
+            c.v[0]
+        };
+        let (mut chip8, _) = run(&rom, |c| c.seed_rng(7), 0);
+        let first = draw(&mut chip8);
+        chip8.warm_reset();
+        let second = draw(&mut chip8);
+        assert_ne!(second, first);
+        chip8.cold_boot();
+        assert_eq!(draw(&mut chip8), first);
+        chip8.warm_reset();
+        assert_eq!(draw(&mut chip8), second);
+    }
+
+    #[test]
+    fn cold_boot_redraws_garbage_and_warm_reset_keeps_it() {
+        let (mut chip8, _) = run(&[0x60, 0x05], |c| c.set_boot_garbage(true), 0);
+        chip8.cold_boot();
+        let garbage = chip8.mem.clone();
+        assert_eq!(&garbage[0x200..0x202], &[0x60, 0x05]);
+        chip8.tick().unwrap();
+        chip8.warm_reset();
+        assert_eq!(chip8.mem, garbage);
+        assert_eq!((chip8.v[0], chip8.pc), (0, 0x200));
+        chip8.cold_boot();
+        assert_ne!(chip8.mem[0x300..0x400], garbage[0x300..0x400]);
+        assert_eq!(&chip8.mem[0x200..0x202], &[0x60, 0x05]);
+    }
+
+    #[test]
+    fn custom_rng_carries_on_across_cold_boots() {
+        let rom = [0xC0, 0xFF];
+        let step = rand::rngs::mock::StepRng::new(1, 1);
+        let (mut chip8, _) = run(&rom, |c| c.set_rng(step), 1);
+        chip8.cold_boot();
+        chip8.tick().unwrap();
+        assert_eq!(chip8.v[0], 2);
+    }
+
+    #[test]
+    fn extended_opcodes_are_unknown_on_platforms_without_them() {
+        // high, then scu 1
+        let rom = [0x00, 0xFF, 0x00, 0xD1];
+        let (chip8, result) = run(&rom, |_| {}, 1);
+        let unknown = |pc, opcode| Err(UnknownOpcode { pc, opcode }.into());
+        assert_eq!(result, unknown(0x200, 0x00FF));
+        assert!(!chip8.hires);
+        let (chip8, result) = run(&rom, |c| c.set_platform(Platform::SuperChip), 2);
+        assert_eq!(result, unknown(0x202, 0x00D1));
+        assert!(chip8.hires);
+        let (_, result) = run(&rom, |c| c.set_platform(Platform::XoChip), 2);
+        assert_eq!(result, Ok(()));
+        assert_eq!(Quirks::default(), Platform::default().quirks());
+    }
+
+    #[test]
+    fn xo_chip_saves_and_loads_register_ranges_in_either_order() {
+        // v1 = 1, v2 = 2, v3 = 3, i = 0x300, save v1 - v3, i = 0x310, save v3 - v1
+        let save = [
+            0x61, 0x01, 0x62, 0x02, 0x63, 0x03, 0xA3, 0x00, 0x51, 0x32, 0xA3, 0x10, 0x53, 0x12,
+        ];
+        let (chip8, result) = run(&save, |c| c.set_platform(Platform::XoChip), 7);
+        assert_eq!(result, Ok(()));
+        assert_eq!(chip8.mem[0x300..0x303], [1, 2, 3]);
+        assert_eq!(chip8.mem[0x310..0x313], [3, 2, 1]);
+        assert_eq!(chip8.index(), 0x310);
+        // i = 0x204, load va - vc from the ld v3, 3 and ld i, 0x300 words
+        let load = [0xA2, 0x04, 0x5A, 0xC3, 0x63, 0x03, 0xA3, 0x00];
+        let (chip8, _) = run(&load, |c| c.set_platform(Platform::XoChip), 2);
+        assert_eq!(chip8.v[0xA..=0xC], [0x63, 0x03, 0xA3]);
+        let (_, result) = run(&load, |_| {}, 2);
+        assert_eq!(
+            result,
+            Err(UnknownOpcode {
+                pc: 0x202,
+                opcode: 0x5AC3
+            }
+            .into())
+        );
+    }
+
+    #[test]
+    fn new_timing_replaces_the_decoded_costs() {
+        // ld v0, 0 and jp 200, so ld runs again on every other tick
+        let game = [0x60, 0x00, 0x12, 0x00];
+        let (mut chip8, _) = run(&game, |c| c.set_timing(TimingTable::vip()), 0);
+        assert_eq!(chip8.tick().unwrap().cost, 27);
+        chip8.tick().unwrap();
+        assert_eq!(chip8.tick().unwrap().cost, 27);
+        chip8.tick().unwrap();
+        chip8.set_timing(TimingTable::per_frame(10));
+        assert_eq!(chip8.tick().unwrap().cost, 1_000_000 / 60 / 10);
+    }
+
+    #[test]
+    fn press_order_ignores_keys_tapped_before_the_wait() {
+        // ld v0, 0, then ld v1, k
+        let game = [0x60, 0x00, 0xF1, 0x0A];
+        let setup = |c: &mut Chip8| {
+            c.set_key_priority(KeyPriority::PressOrder);
+            c.key_down(3);
+            c.key_up(3);
+        };
+        let (mut chip8, _) = run(&game, setup, 3);
+        assert_eq!(chip8.pc(), 0x202);
+        chip8.key_down(5);
+        chip8.tick().unwrap();
+        chip8.key_up(5);
+        chip8.tick().unwrap();
+        assert_eq!(chip8.registers()[1], 5);
+        assert_eq!(chip8.pc(), 0x204);
+    }
+
+    #[test]
+    fn known_games_load_on_their_platform_unless_one_was_given() {
+        let scrolling = include_bytes!("../test/8-scrolling.ch8");
+        let mut chip8 = Chip8::new();
+        chip8.load(scrolling).unwrap();
+        assert_eq!(chip8.platform(), Platform::SuperChip);
+        assert_eq!(chip8.quirks(), Platform::SuperChip.quirks());
+
+        let mut chip8 = Chip8::builder().platform(Platform::Chip8).build();
+        chip8.load(scrolling).unwrap();
+        assert_eq!(chip8.platform(), Platform::Chip8);
+        let mut chip8 = Chip8::builder().detect_platform(false).build();
+        chip8.load(scrolling).unwrap();
+        assert_eq!(chip8.platform(), Platform::Chip8);
+    }
+
+    #[test]
+    fn lores_scrolls_half_as_far_with_the_half_scroll_quirk() {
+        // v0 = 0, i = the 80 byte at 20a, drw v0, v0, 1, scr, scd 4
+        let rom = [
+            0x60, 0x00, 0xA2, 0x0A, 0xD0, 0x01, 0x00, 0xFB, 0x00, 0xC4, 0x80, 0x00,
+        ];
+        let lit_pixel = |platform| {
+            let setup = |c: &mut Chip8| c.set_platform(platform);
+            let (chip8, _) = run(&rom, setup, 5);
+            let (width, _) = chip8.dimensions();
+            let lit = chip8.pixels().iter().position(|&p| p != 0).unwrap();
+            (lit % width, lit / width)
+        };
+        assert_eq!(lit_pixel(Platform::SuperChip), (2, 2));
+        assert_eq!(lit_pixel(Platform::XoChip), (4, 4));
+    }
+
+    #[test]
+    fn cycling_quirks_keeps_the_game_running_as_it_was() {
+        // hires, plane 2, i = the 80 byte at 20c, drw v0, v0, 1, then loop
+        let rom = [
+            0x00, 0xFF, 0xF2, 0x01, 0xA2, 0x0C, 0xD0, 0x01, 0x60, 0x00, 0x12, 0x08, 0x80,
+        ];
+        let (mut chip8, _) = run(&rom, |c| c.set_platform(Platform::XoChip), 4);
+        let pixels = chip8.pixels();
+        assert_eq!(pixels[0], 2);
+
+        let cycled: Vec<_> = (0..4).map(|_| chip8.cycle_quirks()).collect();
+        assert_eq!(
+            cycled,
+            [
+                Platform::Chip8,
+                Platform::Chip48,
+                Platform::SuperChip,
+                Platform::XoChip
+            ]
+        );
+        assert_eq!(chip8.quirks(), Platform::XoChip.quirks());
+        chip8.cycle_quirks();
+        assert_eq!(chip8.quirks(), Platform::Chip8.quirks());
+        assert_eq!(chip8.quirk_preset(), Some(Platform::Chip8));
+        assert_eq!(chip8.platform(), Platform::XoChip);
+        (0..4).for_each(|_| chip8.tick().map(|_| ()).unwrap());
+        assert_eq!(chip8.dimensions(), (HIRES_WIDTH, HIRES_HEIGHT));
+        assert_eq!(chip8.pixels(), pixels);
+
+        // a mix of quirks starts over at the first platform's
+        chip8.set_quirks(Quirks {
+            clipping: false,
+            ..Platform::Chip8.quirks()
+        });
+        assert_eq!(chip8.quirk_preset(), None);
+        assert_eq!(chip8.cycle_quirks(), Platform::Chip8);
+    }
+
+    // the registers, i, pc, recent pcs and screen hash after a frame
+    #[cfg(feature = "jit")]
+    type Frame = ([u8; REGISTER_COUNT], u16, u16, Vec<u16>, u64);
+
+    // runs a game frame by frame with or without the jit, recording the machine after each one
+    #[cfg(feature = "jit")]
+    fn frames(game: &[u8], jit: bool) -> Vec<Frame> {
+        let mut chip8 = Chip8::builder().seed(1).build();
+        if jit {
+            chip8.enable_jit().unwrap();
+        }
+        chip8.load(game).unwrap();
+        (0..60)
+            .map(|_| {
+                chip8.step_frame(1_000_000 / 60).unwrap();
+                let history = chip8.pc_history.iter().copied().collect();
+                (chip8.v, chip8.ir, chip8.pc, history, chip8.screen_hash())
+            })
+            .collect()
+    }
+
+    #[test]
+    #[cfg(feature = "jit")]
+    fn jit_runs_games_like_the_interpreter() {
+        let corax = include_bytes!("../test/3-corax+.ch8");
+        assert_eq!(frames(corax, true), frames(corax, false));
+        // arithmetic setting vf every way it can, looping back to 204
+        let flags = [
+            0x60, 0x01, 0x61, 0xFF, 0x80, 0x14, 0x82, 0x15, 0x83, 0x06, 0x84, 0x0E, 0x81, 0x07,
+            0x85, 0x12, 0x75, 0x03, 0x83, 0xF4, 0x8F, 0x14, 0x12, 0x04,
+        ];
+        assert_eq!(frames(&flags, true), frames(&flags, false));
+    }
+
+    #[test]
+    fn super_chip_draws_16x16_sprites_and_counts_colliding_rows() {
+        let schip = |c: &mut Chip8| c.set_platform(Platform::SuperChip);
+        // high, i = 20c, drw v0, v1, 0 twice, then 32 bytes of sprite
+        let mut rom = vec![
+            0x00, 0xFF, 0xA2, 0x0C, 0xD0, 0x10, 0xD0, 0x10, 0x12, 0x08, 0, 0,
+        ];
+        rom.extend([0xFF; 32]);
+        let (chip8, _) = run(&rom, schip, 3);
+        assert_eq!(chip8.pixels_lit(), 16 * 16);
+        assert_eq!(
+            (chip8.pixel(15, 15), chip8.pixel(16, 0), chip8.pixel(0, 16)),
+            (1, 0, 0)
+        );
+        assert_eq!(chip8.registers()[0xF], 0);
+        // every row collides the second time
+        let (chip8, _) = run(&rom, schip, 4);
+        assert_eq!(chip8.pixels_lit(), 0);
+        assert_eq!(chip8.registers()[0xF], 16);
+
+        // rows clipped at the bottom count too, v1 = 120 leaves 8 of them on the screen
+        let mut clipped = vec![
+            0x61, 0x78, 0x00, 0xFF, 0xA2, 0x0C, 0xD0, 0x10, 0x12, 0x08, 0, 0,
+        ];
+        clipped.extend([0xFF; 32]);
+        let (chip8, _) = run(&clipped, schip, 4);
+        assert_eq!(chip8.pixels_lit(), 16 * 8);
+        assert_eq!(chip8.registers()[0xF], 8);
+
+        // in lo-res, dxy0 draws 8x16 and vf only says whether anything collided
+        let mut lores = vec![0xA2, 0x08, 0xD0, 0x00, 0xD0, 0x00, 0x12, 0x06];
+        lores.extend([0xFF; 16]);
+        let (chip8, _) = run(&lores, schip, 2);
+        assert_eq!(chip8.pixels_lit(), 8 * 16);
+        let (chip8, _) = run(&lores, schip, 3);
+        assert_eq!(chip8.pixels_lit(), 0);
+        assert_eq!(chip8.registers()[0xF], 1);
+    }
+
+    #[test]
+    fn xo_chip_draws_and_clears_only_the_selected_planes() {
+        let xo_chip = |c: &mut Chip8| c.set_platform(Platform::XoChip);
+        // plane 2, i = 20e, drw v0, v0, 1, plane 3, drw v0, v0, 1, plane 1, cls
+        let rom = [
+            0xF2, 0x01, 0xA2, 0x0E, 0xD0, 0x01, 0xF3, 0x01, 0xD0, 0x01, 0xF1, 0x01, 0x00, 0xE0,
+            0x80, 0x40,
+        ];
+        let (chip8, _) = run(&rom, xo_chip, 3);
+        assert_eq!((chip8.pixel(0, 0), chip8.pixel(1, 0)), (2, 0));
+        // with both planes the first one draws 80 and the second 40, the byte after it
+        let (chip8, _) = run(&rom, xo_chip, 5);
+        assert_eq!((chip8.pixel(0, 0), chip8.pixel(1, 0)), (3, 2));
+        assert_eq!(chip8.registers()[0xF], 0);
+        let (chip8, _) = run(&rom, xo_chip, 7);
+        assert_eq!((chip8.pixel(0, 0), chip8.pixel(1, 0)), (2, 2));
+    }
+
+    #[test]
+    fn wait_for_key_completes_on_release() {
+        // ld v0, k, ld v1, k
+        let (mut chip8, _) = run(&[0xF0, 0x0A, 0xF1, 0x0A], |_| {}, 0);
+        chip8.key_down(4);
+        for _ in 0..3 {
+            chip8.tick().unwrap();
+        }
+        assert_eq!((chip8.pc(), chip8.registers()[0]), (0x200, 0));
+        chip8.key_up(4);
+        chip8.tick().unwrap();
+        assert_eq!((chip8.pc(), chip8.registers()[0]), (0x202, 4));
+        // the next wait needs a press of its own
+        chip8.tick().unwrap();
+        assert_eq!(chip8.pc(), 0x202);
+        chip8.key_down(6);
+        chip8.tick().unwrap();
+        chip8.key_up(6);
+        chip8.tick().unwrap();
+        assert_eq!((chip8.pc(), chip8.registers()[1]), (0x204, 6));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_round_trips_a_running_machine() {
+        let corax = include_bytes!("../test/3-corax+.ch8");
+        let (mut chip8, _) = run(corax, |c| c.set_platform(Platform::SuperChip), 200);
+        chip8.key_down(7);
+        let json = serde_json::to_string(&chip8).unwrap();
+        let restored: Chip8 = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.save_state().unwrap(), chip8.save_state().unwrap());
+        assert_eq!(restored.pixels(), chip8.pixels());
+        assert_eq!(restored.keypad(), chip8.keypad());
+        assert_eq!(restored.platform(), Platform::SuperChip);
+        assert_eq!(
+            (restored.quirks(), &restored.timing),
+            (chip8.quirks(), &chip8.timing)
+        );
+    }
+
+    #[test]
+    fn load_xo_chip_needs_64k_of_memory() {
+        // plane 3, a word only xo-chip has
+        let game = [0xF3, 0x01];
+        let mut chip8 = Chip8::new();
+        assert_eq!(
+            chip8.load_xo_chip(&game),
+            Err(Chip8Error::MemoryTooSmall {
+                size: 0x1000,
+                required: 0x10000
+            })
+        );
+        assert_eq!(chip8.platform(), Platform::Chip8);
+
+        let mut chip8 = Chip8::builder().memory_size(MemorySize::Kb64).build();
+        chip8.load_xo_chip(&game).unwrap();
+        assert_eq!(chip8.platform(), Platform::XoChip);
+        assert_eq!(chip8.quirks(), Platform::XoChip.quirks());
+        chip8.tick().unwrap();
+        assert_eq!(chip8.planes, 3);
+        // i reaches past 4k
+        chip8.set_index(0xFFFF);
+        assert_eq!(chip8.index(), 0xFFFF);
+    }
+
+    #[test]
+    fn chip_8x_colors_the_screen_and_reads_the_second_keypad() {
+        let game = [
+            0x60, 0x12, 0x61, 0x01, 0x62, 0x05, // v0 = 12, v1 = 01, v2 = yellow
+            0xB0, 0x20, // zones 2 and 3 of zone row 1
+            0x02, 0xA0, // the next background
+            0x63, 0x77, 0x64, 0x11, 0x53, 0x41, // nadd v3, v4 wraps each nibble
+            0x65, 0x18, 0x66, 0x14, 0x67, 0x03, // v5 = 18, v6 = 14, v7 = violet
+            0xB5, 0x72, // 2 rows of column 3, from row 20
+            0xE8, 0xF2, 0x69, 0x01, // skp2 v8 skips v9 = 1
+            0x13, 0x1C,
+        ];
+        let mut chip8 = Chip8::with_platform(Platform::Chip8X);
+        chip8.load(&game).unwrap();
+        chip8.key_down(0x10);
+        assert_eq!(chip8.keypad(), &[false; KEY_COUNT]);
+        while chip8.pc() != 0x31C {
+            chip8.tick().unwrap();
+        }
+        let colors = *chip8.colors().unwrap();
+        assert_eq!(colors.background, 1);
+        assert_eq!(
+            [(16, 4), (31, 7), (15, 4), (32, 4), (16, 3), (16, 8)]
+                .map(|(x, y)| colors.color_at(x, y)),
+            [5, 5, BOOT_COLOR, BOOT_COLOR, BOOT_COLOR, BOOT_COLOR]
+        );
+        assert_eq!(
+            [(24, 20), (31, 21), (24, 19), (24, 22)].map(|(x, y)| colors.color_at(x, y)),
+            [3, 3, BOOT_COLOR, BOOT_COLOR]
+        );
+        assert_eq!(chip8.registers()[3], 0x00);
+        assert_eq!(chip8.registers()[9], 0);
+
+        let mut restored = Chip8::with_platform(Platform::Chip8X);
+        restored.load_state(&chip8.save_state().unwrap()).unwrap();
+        assert_eq!(restored.colors(), Some(&colors));
+        assert!(restored.second_keypad()[0]);
+        // the same words are unknown opcodes elsewhere
+        let (_, result) = run(&[0x53, 0x41], |_| {}, 1);
+        assert!(result.is_err());
+        assert_eq!(Chip8::new().colors(), None);
+    }
 }
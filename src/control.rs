@@ -0,0 +1,243 @@
+#[cfg(unix)]
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::Path,
+};
+use std::{path::PathBuf, str::FromStr};
+
+// something a control client asked for, through the socket or the http server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Pause,
+    Resume,
+    // loads the game at the path instead and boots it
+    Load(PathBuf),
+    // save states next to the game, e.g. slot 1 of game.ch8 is game.1.state
+    SaveState(String),
+    LoadState(String),
+    // the screen as a portable bitmap
+    Screenshot(PathBuf),
+    // every address the bytes are found at, e.g. to look for a score
+    Find(Vec<u8>),
+    Quit,
+}
+impl FromStr for Command {
+    type Err = ();
+
+    // "pause", "resume", "load PATH", "savestate SLOT", "loadstate SLOT", "screenshot PATH",
+    // "find HEX" or "quit"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, arg) = match s.trim().split_once(' ') {
+            Some((name, arg)) => (name, arg.trim()),
+            None => (s.trim(), ""),
+        };
+        let command = match (name, arg) {
+            ("pause", "") => Command::Pause,
+            ("resume", "") => Command::Resume,
+            ("quit", "") => Command::Quit,
+            (_, "") => return Err(()),
+            ("load", path) => Command::Load(path.into()),
+            ("savestate", slot) => Command::SaveState(slot.to_string()),
+            ("loadstate", slot) => Command::LoadState(slot.to_string()),
+            ("screenshot", path) => Command::Screenshot(path.into()),
+            ("find", hex) => Command::Find(hex_bytes(hex).ok_or(())?),
+            _ => return Err(()),
+        };
+        Ok(command)
+    }
+}
+
+// bytes written as hex digits, spaces between them allowed, e.g. "dead beef" or "de ad be ef"
+pub(crate) fn hex_bytes(hex: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = hex.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    if digits.is_empty() || !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    digits
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+// a command and the client waiting for its answer
+#[cfg(unix)]
+pub struct Request {
+    pub command: Command,
+    client: UnixStream,
+}
+#[cfg(unix)]
+impl Request {
+    // answers "ok", "ok" and what the command found, or "error: ..." on a line of its own
+    pub fn reply(mut self, result: Result<String, String>) {
+        answer(&mut self.client, result);
+    }
+}
+
+#[cfg(unix)]
+fn answer(client: &mut UnixStream, result: Result<String, String>) {
+    let line = match result {
+        Ok(found) if found.is_empty() => "ok\n".to_string(),
+        Ok(found) => format!("ok {found}\n"),
+        Err(e) => format!("error: {e}\n"),
+    };
+    // a client that left doesn't need the answer
+    let _ = client.write_all(line.as_bytes());
+}
+
+// a unix socket that external scripts and test harnesses drive a running emulator through,
+// one command per line, e.g. with `echo pause | socat - UNIX-CONNECT:chip8.sock`. it never
+// blocks, the frame loop polls it once per frame
+#[cfg(unix)]
+pub struct ControlSocket {
+    listener: UnixListener,
+    path: PathBuf,
+    clients: Vec<Client>,
+}
+#[cfg(unix)]
+impl ControlSocket {
+    pub fn bind(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let listener = match UnixListener::bind(&path) {
+            // left behind by an emulator that didn't shut down, unless one is still listening
+            Err(e) if e.kind() == ErrorKind::AddrInUse && UnixStream::connect(&path).is_err() => {
+                std::fs::remove_file(&path)?;
+                UnixListener::bind(&path)?
+            }
+            result => result?,
+        };
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            path,
+            clients: Vec::new(),
+        })
+    }
+
+    // the commands that came in since the last poll, bad ones are answered right away
+    pub fn poll(&mut self) -> Vec<Request> {
+        while let Ok((stream, _)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_ok() {
+                self.clients.push(Client {
+                    stream,
+                    buffer: Vec::new(),
+                });
+            }
+        }
+
+        let mut requests = Vec::new();
+        self.clients.retain_mut(|client| {
+            let open = client.receive();
+            while let Some(end) = client.buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = client.buffer.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match line.parse() {
+                    Ok(command) => {
+                        if let Ok(client) = client.stream.try_clone() {
+                            requests.push(Request { command, client });
+                        }
+                    }
+                    Err(()) => answer(
+                        &mut client.stream,
+                        Err(format!("unknown command {}", line.trim())),
+                    ),
+                }
+            }
+            open
+        });
+        requests
+    }
+}
+#[cfg(unix)]
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(unix)]
+struct Client {
+    stream: UnixStream,
+    buffer: Vec<u8>, // a line still being received
+}
+#[cfg(unix)]
+impl Client {
+    // reads what arrived, false once the client hung up
+    fn receive(&mut self) -> bool {
+        let mut chunk = [0; 256];
+        loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return false,
+                Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return true,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => return false,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!("pause".parse(), Ok(Command::Pause));
+        assert_eq!(" resume \n".parse(), Ok(Command::Resume));
+        assert_eq!(
+            "load games/pong.ch8".parse(),
+            Ok(Command::Load("games/pong.ch8".into()))
+        );
+        assert_eq!("savestate 1".parse(), Ok(Command::SaveState("1".into())));
+        assert_eq!("loadstate a".parse(), Ok(Command::LoadState("a".into())));
+        assert_eq!(
+            "screenshot s.pbm".parse(),
+            Ok(Command::Screenshot("s.pbm".into()))
+        );
+        assert_eq!(
+            "find dead be ef".parse(),
+            Ok(Command::Find(vec![0xDE, 0xAD, 0xBE, 0xEF]))
+        );
+        assert_eq!("find abc".parse::<Command>(), Err(()));
+        assert_eq!("find zz".parse::<Command>(), Err(()));
+        assert_eq!("quit".parse(), Ok(Command::Quit));
+        assert_eq!("load".parse::<Command>(), Err(()));
+        assert_eq!("pause now".parse::<Command>(), Err(()));
+        assert_eq!("jump".parse::<Command>(), Err(()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn socket_answers_each_command() {
+        let path = std::env::temp_dir().join(format!("chip8-test-{}.sock", std::process::id()));
+        let mut socket = ControlSocket::bind(&path).unwrap();
+        let mut client = UnixStream::connect(&path).unwrap();
+        client.write_all(b"jump\npause\n").unwrap();
+
+        // the socket never blocks, so poll until the command made it through
+        let mut requests = Vec::new();
+        for _ in 0..100 {
+            requests = socket.poll();
+            if !requests.is_empty() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(requests.len(), 1);
+        let request = requests.remove(0);
+        assert_eq!(request.command, Command::Pause);
+        request.reply(Ok(String::new()));
+
+        let mut answers = String::new();
+        client.shutdown(std::net::Shutdown::Write).unwrap();
+        socket.poll();
+        drop(socket);
+        client.read_to_string(&mut answers).unwrap();
+        assert_eq!(answers, "error: unknown command jump\nok\n");
+        assert!(!path.exists());
+    }
+}
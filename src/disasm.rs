@@ -0,0 +1,383 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use crate::instruction::{self, AluOp, Instruction};
+
+// a single disassembled instruction, as shown in a debugger's instruction view
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListingEntry {
+    pub addr: u16,
+    pub op: u16,
+    pub text: String,
+    pub is_current: bool,
+    pub was_executed: bool,
+}
+
+// mnemonics follow the cowgod reference, with registers and addresses filled in
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Instruction::*;
+        match *self {
+            Clear => write!(f, "cls"),
+            Return => write!(f, "ret"),
+            ScrollDown(n) => write!(f, "scd {n}"),
+            ScrollUp(n) => write!(f, "scu {n}"),
+            ScrollRight => write!(f, "scr"),
+            ScrollLeft => write!(f, "scl"),
+            Exit => write!(f, "exit"),
+            LowRes => write!(f, "low"),
+            HighRes => write!(f, "high"),
+            Sys(nnn) => write!(f, "sys 0x{nnn:03x}"),
+            Jump(nnn) => write!(f, "jp 0x{nnn:03x}"),
+            Call(nnn) => write!(f, "call 0x{nnn:03x}"),
+            SkipEqual(x, nn) => write!(f, "se v{x:x}, 0x{nn:02x}"),
+            SkipNotEqual(x, nn) => write!(f, "sne v{x:x}, 0x{nn:02x}"),
+            SkipEqualRegisters(x, y) => write!(f, "se v{x:x}, v{y:x}"),
+            AddNibbles(x, y) => write!(f, "nadd v{x:x}, v{y:x}"),
+            SaveRange(x, y) => write!(f, "save v{x:x} - v{y:x}"),
+            LoadRange(x, y) => write!(f, "load v{x:x} - v{y:x}"),
+            Load(x, nn) => write!(f, "ld v{x:x}, 0x{nn:02x}"),
+            Add(x, nn) => write!(f, "add v{x:x}, 0x{nn:02x}"),
+            Alu(x, y, op) => write!(f, "{} v{x:x}, v{y:x}", alu_mnemonic(op)),
+            SkipNotEqualRegisters(x, y) => write!(f, "sne v{x:x}, v{y:x}"),
+            LoadIndex(nnn) => write!(f, "ld i, 0x{nnn:03x}"),
+            JumpOffset(_, nnn) => write!(f, "jp v0, 0x{nnn:03x}"),
+            Random(x, nn) => write!(f, "rnd v{x:x}, 0x{nn:02x}"),
+            Draw(x, y, n) => write!(f, "drw v{x:x}, v{y:x}, {n}"),
+            SkipKey(x) => write!(f, "skp v{x:x}"),
+            SkipNotKey(x) => write!(f, "sknp v{x:x}"),
+            SkipKey2(x) => write!(f, "skp2 v{x:x}"),
+            SkipNotKey2(x) => write!(f, "sknp2 v{x:x}"),
+            LoadIndexLong => write!(f, "ld i, long"),
+            Audio => write!(f, "audio"),
+            Plane(n) => write!(f, "plane {n}"),
+            LoadDelay(x) => write!(f, "ld v{x:x}, dt"),
+            WaitKey(x) => write!(f, "ld v{x:x}, k"),
+            SetDelay(x) => write!(f, "ld dt, v{x:x}"),
+            SetSound(x) => write!(f, "ld st, v{x:x}"),
+            AddIndex(x) => write!(f, "add i, v{x:x}"),
+            Font(x) => write!(f, "ld f, v{x:x}"),
+            LargeFont(x) => write!(f, "ld hf, v{x:x}"),
+            Pitch(x) => write!(f, "pitch v{x:x}"),
+            Bcd(x) => write!(f, "ld b, v{x:x}"),
+            Store(x) => write!(f, "ld [i], v{x:x}"),
+            Restore(x) => write!(f, "ld v{x:x}, [i]"),
+            StoreFlags(x) => write!(f, "ld r, v{x:x}"),
+            RestoreFlags(x) => write!(f, "ld v{x:x}, r"),
+        }
+    }
+}
+
+fn alu_mnemonic(op: AluOp) -> &'static str {
+    match op {
+        AluOp::Load => "ld",
+        AluOp::Or => "or",
+        AluOp::And => "and",
+        AluOp::Xor => "xor",
+        AluOp::Add => "add",
+        AluOp::Sub => "sub",
+        AluOp::Shr => "shr",
+        AluOp::SubN => "subn",
+        AluOp::Shl => "shl",
+    }
+}
+
+// the instruction a listing shows for an opcode. 5xyN runs as 5xy0 on the vip, but only 5xy0 is
+// listed as one so data stands out
+fn listed(op: u16) -> Option<Instruction> {
+    match instruction::decode(op) {
+        Ok(Instruction::SkipEqualRegisters(..)) if op & 0x000F != 0 => None,
+        decoded => decoded.ok(),
+    }
+}
+
+// words that aren't instructions are shown as data
+pub fn disassemble(op: u16) -> String {
+    match listed(op) {
+        Some(instruction) => instruction.to_string(),
+        None => format!("dw 0x{op:04x}"),
+    }
+}
+
+// addresses and mnemonics of the words in memory[start..end]. xo-chip's f000 nnnn takes its
+// address from the next word, so it's listed as one instruction
+pub fn disassemble_range(memory: &[u8], start: usize, end: usize) -> Vec<(u16, String)> {
+    let end = end.min(memory.len());
+    let word = |addr: usize| u16::from_be_bytes([memory[addr], memory[addr + 1]]);
+    let mut lines = Vec::new();
+    let mut addr = start;
+    while addr + 1 < end {
+        let op = word(addr);
+        let (text, size) = match listed(op) {
+            Some(Instruction::LoadIndexLong) if addr + 3 < end => {
+                (format!("ld i, 0x{:04x}", word(addr + 2)), 4)
+            }
+            _ => (disassemble(op), 2),
+        };
+        lines.push((addr as u16, text));
+        addr += size;
+    }
+    lines
+}
+
+// the class of an instruction, its operands left symbolic. tables like the instruction timing
+// keep a value per class, indexed by it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InstructionClass {
+    Clear,
+    Return,
+    ScrollDown,
+    ScrollUp,
+    ScrollRight,
+    ScrollLeft,
+    Exit,
+    LowRes,
+    HighRes,
+    Sys,
+    Jump,
+    Call,
+    SkipEqual,
+    SkipNotEqual,
+    SkipEqualRegisters,
+    AddNibbles,
+    SaveRange,
+    LoadRange,
+    Load,
+    Add,
+    LoadRegister,
+    Or,
+    And,
+    Xor,
+    AddRegister,
+    Sub,
+    Shr,
+    SubN,
+    Shl,
+    SkipNotEqualRegisters,
+    LoadIndex,
+    JumpOffset,
+    Random,
+    Draw,
+    SkipKey,
+    SkipNotKey,
+    SkipKey2,
+    SkipNotKey2,
+    LoadIndexLong,
+    Audio,
+    Plane,
+    LoadDelay,
+    WaitKey,
+    SetDelay,
+    SetSound,
+    AddIndex,
+    Font,
+    LargeFont,
+    Pitch,
+    Bcd,
+    Store,
+    Restore,
+    StoreFlags,
+    RestoreFlags,
+    // words that aren't instructions
+    Data,
+}
+impl InstructionClass {
+    pub const COUNT: usize = 55;
+    // every class, in declaration order
+    pub const ALL: [Self; Self::COUNT] = [
+        Self::Clear,
+        Self::Return,
+        Self::ScrollDown,
+        Self::ScrollUp,
+        Self::ScrollRight,
+        Self::ScrollLeft,
+        Self::Exit,
+        Self::LowRes,
+        Self::HighRes,
+        Self::Sys,
+        Self::Jump,
+        Self::Call,
+        Self::SkipEqual,
+        Self::SkipNotEqual,
+        Self::SkipEqualRegisters,
+        Self::AddNibbles,
+        Self::SaveRange,
+        Self::LoadRange,
+        Self::Load,
+        Self::Add,
+        Self::LoadRegister,
+        Self::Or,
+        Self::And,
+        Self::Xor,
+        Self::AddRegister,
+        Self::Sub,
+        Self::Shr,
+        Self::SubN,
+        Self::Shl,
+        Self::SkipNotEqualRegisters,
+        Self::LoadIndex,
+        Self::JumpOffset,
+        Self::Random,
+        Self::Draw,
+        Self::SkipKey,
+        Self::SkipNotKey,
+        Self::SkipKey2,
+        Self::SkipNotKey2,
+        Self::LoadIndexLong,
+        Self::Audio,
+        Self::Plane,
+        Self::LoadDelay,
+        Self::WaitKey,
+        Self::SetDelay,
+        Self::SetSound,
+        Self::AddIndex,
+        Self::Font,
+        Self::LargeFont,
+        Self::Pitch,
+        Self::Bcd,
+        Self::Store,
+        Self::Restore,
+        Self::StoreFlags,
+        Self::RestoreFlags,
+        Self::Data,
+    ];
+
+    pub fn of(op: u16) -> Self {
+        use Instruction::*;
+        let Some(instruction) = listed(op) else {
+            return Self::Data;
+        };
+        match instruction {
+            Clear => Self::Clear,
+            Return => Self::Return,
+            ScrollDown(_) => Self::ScrollDown,
+            ScrollUp(_) => Self::ScrollUp,
+            ScrollRight => Self::ScrollRight,
+            ScrollLeft => Self::ScrollLeft,
+            Exit => Self::Exit,
+            LowRes => Self::LowRes,
+            HighRes => Self::HighRes,
+            Sys(_) => Self::Sys,
+            Jump(_) => Self::Jump,
+            Call(_) => Self::Call,
+            SkipEqual(..) => Self::SkipEqual,
+            SkipNotEqual(..) => Self::SkipNotEqual,
+            SkipEqualRegisters(..) => Self::SkipEqualRegisters,
+            AddNibbles(..) => Self::AddNibbles,
+            SaveRange(..) => Self::SaveRange,
+            LoadRange(..) => Self::LoadRange,
+            Load(..) => Self::Load,
+            Add(..) => Self::Add,
+            Alu(_, _, AluOp::Load) => Self::LoadRegister,
+            Alu(_, _, AluOp::Or) => Self::Or,
+            Alu(_, _, AluOp::And) => Self::And,
+            Alu(_, _, AluOp::Xor) => Self::Xor,
+            Alu(_, _, AluOp::Add) => Self::AddRegister,
+            Alu(_, _, AluOp::Sub) => Self::Sub,
+            Alu(_, _, AluOp::Shr) => Self::Shr,
+            Alu(_, _, AluOp::SubN) => Self::SubN,
+            Alu(_, _, AluOp::Shl) => Self::Shl,
+            SkipNotEqualRegisters(..) => Self::SkipNotEqualRegisters,
+            LoadIndex(_) => Self::LoadIndex,
+            JumpOffset(..) => Self::JumpOffset,
+            Random(..) => Self::Random,
+            Draw(..) => Self::Draw,
+            SkipKey(_) => Self::SkipKey,
+            SkipNotKey(_) => Self::SkipNotKey,
+            SkipKey2(_) => Self::SkipKey2,
+            SkipNotKey2(_) => Self::SkipNotKey2,
+            LoadIndexLong => Self::LoadIndexLong,
+            Audio => Self::Audio,
+            Plane(_) => Self::Plane,
+            LoadDelay(_) => Self::LoadDelay,
+            WaitKey(_) => Self::WaitKey,
+            SetDelay(_) => Self::SetDelay,
+            SetSound(_) => Self::SetSound,
+            AddIndex(_) => Self::AddIndex,
+            Font(_) => Self::Font,
+            LargeFont(_) => Self::LargeFont,
+            Pitch(_) => Self::Pitch,
+            Bcd(_) => Self::Bcd,
+            Store(_) => Self::Store,
+            Restore(_) => Self::Restore,
+            StoreFlags(_) => Self::StoreFlags,
+            RestoreFlags(_) => Self::RestoreFlags,
+        }
+    }
+    pub fn mnemonic(self) -> &'static str {
+        MNEMONICS[self as usize]
+    }
+    // the class with the mnemonic, e.g. "drw vx, vy, n"
+    pub fn from_mnemonic(mnemonic: &str) -> Option<Self> {
+        let class = MNEMONICS.iter().position(|&known| known == mnemonic)?;
+        Some(Self::ALL[class])
+    }
+}
+
+// by class, in declaration order
+const MNEMONICS: [&str; InstructionClass::COUNT] = [
+    "cls",
+    "ret",
+    "scd n",
+    "scu n",
+    "scr",
+    "scl",
+    "exit",
+    "low",
+    "high",
+    "sys nnn",
+    "jp nnn",
+    "call nnn",
+    "se vx, nn",
+    "sne vx, nn",
+    "se vx, vy",
+    "nadd vx, vy",
+    "save vx - vy",
+    "load vx - vy",
+    "ld vx, nn",
+    "add vx, nn",
+    "ld vx, vy",
+    "or vx, vy",
+    "and vx, vy",
+    "xor vx, vy",
+    "add vx, vy",
+    "sub vx, vy",
+    "shr vx, vy",
+    "subn vx, vy",
+    "shl vx, vy",
+    "sne vx, vy",
+    "ld i, nnn",
+    "jp v0, nnn",
+    "rnd vx, nn",
+    "drw vx, vy, n",
+    "skp vx",
+    "sknp vx",
+    "skp2 vx",
+    "sknp2 vx",
+    "ld i, long",
+    "audio",
+    "plane n",
+    "ld vx, dt",
+    "ld vx, k",
+    "ld dt, vx",
+    "ld st, vx",
+    "add i, vx",
+    "ld f, vx",
+    "ld hf, vx",
+    "pitch vx",
+    "ld b, vx",
+    "ld [i], vx",
+    "ld vx, [i]",
+    "ld r, vx",
+    "ld vx, r",
+    "dw nnnn",
+];
+
+// the instruction class of an opcode, with operands left symbolic
+pub fn mnemonic(op: u16) -> &'static str {
+    InstructionClass::of(op).mnemonic()
+}
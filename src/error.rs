@@ -0,0 +1,73 @@
+use crate::{chip8::UnknownOpcode, stack::StackError, state::StateError};
+use core::fmt;
+
+// everything that can go wrong in the core, the machine stops instead of panicking
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Chip8Error {
+    // a call or return past either end of the stack
+    Stack(StackError),
+    // an instruction the platform doesn't have, under OpcodePolicy::Error
+    UnknownOpcode(UnknownOpcode),
+    // a read or write past the end of memory
+    OutOfBounds {
+        pc: u16,
+        addr: usize,
+    },
+    // a game that doesn't fit between the start address and the end of memory
+    RomTooLarge {
+        size: usize,
+        max: usize,
+        start: usize,
+    },
+    // a game for a platform that needs more memory than the machine was built with
+    MemoryTooSmall {
+        size: usize,
+        required: usize,
+    },
+    // a save state that doesn't load
+    State(StateError),
+    // the game drew more random numbers than the replayed recording has
+    ReplayEnded {
+        pc: u16,
+    },
+}
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Chip8Error::Stack(e) => write!(f, "{e}"),
+            Chip8Error::UnknownOpcode(e) => write!(f, "{e}"),
+            Chip8Error::OutOfBounds { pc, addr } => {
+                write!(f, "memory access at 0x{addr:x} out of bounds at 0x{pc:03x}")
+            }
+            Chip8Error::RomTooLarge { size, max, start } => write!(
+                f,
+                "the game is {size} bytes, but only {max} fit in memory after 0x{start:03x}"
+            ),
+            Chip8Error::MemoryTooSmall { size, required } => write!(
+                f,
+                "the game needs {required} bytes of memory, but the machine has {size}"
+            ),
+            Chip8Error::State(e) => write!(f, "{e}"),
+            Chip8Error::ReplayEnded { pc } => {
+                write!(f, "the replayed random numbers ran out at 0x{pc:03x}")
+            }
+        }
+    }
+}
+impl core::error::Error for Chip8Error {}
+impl From<StackError> for Chip8Error {
+    fn from(e: StackError) -> Self {
+        Chip8Error::Stack(e)
+    }
+}
+impl From<UnknownOpcode> for Chip8Error {
+    fn from(e: UnknownOpcode) -> Self {
+        Chip8Error::UnknownOpcode(e)
+    }
+}
+impl From<StateError> for Chip8Error {
+    fn from(e: StateError) -> Self {
+        Chip8Error::State(e)
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{
+    audio::Beeper,
+    frontend::{Frontend, Input, Screen, Sound, Title},
+    platform::Platform,
+    render::Renderer,
+};
+use minifb::{Key, Scale, ScaleMode, Window, WindowOptions};
+
+// a plain window the cpu draws into with minifb, for when there's no gpu or sdl around, e.g. a
+// quick look over ssh with x forwarding. minifb has no sound, it comes from the beeper if there
+// is one
+pub struct FbFrontend {
+    window: Window,
+    buffer: Vec<u32>,
+    held: Vec<Key>,
+    renderer: Renderer,
+    beeper: Option<Box<dyn Beeper>>,
+    title: Title,
+}
+impl FbFrontend {
+    pub fn new(
+        (width, height): (usize, usize),
+        beeper: Option<Box<dyn Beeper>>,
+    ) -> Result<Self, String> {
+        let options = WindowOptions {
+            resize: true,
+            scale: Scale::X16,
+            scale_mode: ScaleMode::AspectRatioStretch,
+            ..WindowOptions::default()
+        };
+        let mut window = Window::new("chip8", width, height, options).map_err(|e| e.to_string())?;
+        // run paces the frames, minifb would otherwise sleep in every update as well
+        window.set_target_fps(0);
+        Ok(Self {
+            window,
+            buffer: Vec::new(),
+            held: Vec::new(),
+            renderer: Renderer::default(),
+            beeper,
+            title: Title::default(),
+        })
+    }
+}
+impl Frontend for FbFrontend {
+    fn poll_input(&mut self) -> Vec<Input> {
+        self.window.update();
+        if !self.window.is_open() {
+            return vec![Input::Quit];
+        }
+        // minifb only tells which keys are down, presses and releases are the changes since the
+        // last poll
+        let held = self.window.get_keys();
+        let mut inputs: Vec<Input> = held
+            .iter()
+            .filter(|key| !self.held.contains(key))
+            .filter_map(|&key| pressed(key))
+            .collect();
+        inputs.extend(
+            self.held
+                .iter()
+                .filter(|key| !held.contains(key))
+                .filter_map(|&key| button(key).map(Input::KeyUp)),
+        );
+        self.held = held;
+        inputs
+    }
+
+    fn present(&mut self, screen: &Screen) {
+        let rgba = self.renderer.render(screen);
+        self.buffer.clear();
+        self.buffer.extend(
+            rgba.chunks_exact(4)
+                .map(|p| u32::from_be_bytes([0, p[0], p[1], p[2]])),
+        );
+        let [r, g, b] = self.renderer.background();
+        self.window.set_background_color(r, g, b);
+        if let Err(e) = self
+            .window
+            .update_with_buffer(&self.buffer, screen.width, screen.height)
+        {
+            eprintln!("error: can't draw the screen: {e}");
+        }
+    }
+
+    fn play(&mut self, sound: &Sound) {
+        if let Some(beeper) = &mut self.beeper {
+            beeper.play(sound);
+        }
+    }
+
+    fn show_ended(&mut self, ended: bool) {
+        self.title.ended = ended;
+        self.window.set_title(&self.title.to_string());
+    }
+
+    fn show_quirks(&mut self, quirks: Option<Platform>) {
+        self.title.quirks = quirks;
+        self.window.set_title(&self.title.to_string());
+    }
+}
+
+// the same keys as the sdl frontend
+fn pressed(key: Key) -> Option<Input> {
+    let input = match key {
+        Key::Escape => Input::Quit,
+        Key::F1 => Input::WarmReset,
+        Key::F2 => Input::ColdBoot,
+        Key::F3 => Input::CycleQuirks,
+        Key::F5 => Input::SaveState,
+        Key::F9 => Input::LoadState,
+        key => Input::KeyDown(button(key)?),
+    };
+    Some(input)
+}
+
+fn button(key: Key) -> Option<usize> {
+    let index = match key {
+        Key::Key1 => 0x1,
+        Key::Key2 => 0x2,
+        Key::Key3 => 0x3,
+        Key::Key4 => 0xC,
+        Key::Q => 0x4,
+        Key::W => 0x5,
+        Key::E => 0x6,
+        Key::R => 0xD,
+        Key::A => 0x7,
+        Key::S => 0x8,
+        Key::D => 0x9,
+        Key::F => 0xE,
+        Key::Z => 0xA,
+        Key::X => 0x0,
+        Key::C => 0xB,
+        Key::V => 0xF,
+        // chip-8x's second keypad, the same way round to the right
+        Key::Key7 => 0x11,
+        Key::Key8 => 0x12,
+        Key::Key9 => 0x13,
+        Key::Key0 => 0x1C,
+        Key::U => 0x14,
+        Key::I => 0x15,
+        Key::O => 0x16,
+        Key::P => 0x1D,
+        Key::J => 0x17,
+        Key::K => 0x18,
+        Key::L => 0x19,
+        Key::Semicolon => 0x1E,
+        Key::M => 0x1A,
+        Key::Comma => 0x10,
+        Key::Period => 0x1B,
+        Key::Slash => 0x1F,
+        _ => return None,
+    };
+    Some(index)
+}
@@ -0,0 +1,703 @@
+#[cfg(any(unix, feature = "http"))]
+use crate::control::Command;
+#[cfg(unix)]
+use crate::control::ControlSocket;
+#[cfg(feature = "http")]
+use crate::server::HttpServer;
+use crate::{
+    chip8::{Chip8, ColorEffect, ColorMap, Halt, PlanePriority, SysCall},
+    platform::Platform,
+};
+use std::{
+    fmt,
+    io::Write,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+
+pub const FRAME_RATE: u32 = 60; // hz
+const FRAME_TIME: Duration = Duration::new(0, 1_000_000_000 / FRAME_RATE);
+
+// something the player did since the last poll
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    // keys 0x10 to 0x1f are chip-8x's second keypad
+    KeyDown(usize),
+    KeyUp(usize),
+    // cpu and display only, memory is kept
+    WarmReset,
+    ColdBoot,
+    SaveState,
+    LoadState,
+    // the next platform's quirks, without a reset
+    CycleQuirks,
+    Quit,
+}
+
+// the display as of the end of a presented frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct Screen {
+    pub pixels: Vec<u8>, // row major, each a bitmask of the planes lit there
+    pub width: usize,
+    pub height: usize,
+    pub color_effect: ColorEffect,
+    pub plane_priority: PlanePriority,
+    pub collided: bool,  // a draw collided since the last frame
+    pub dirty_rows: u64, // bitmask of the rows that changed since the last frame
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    pub colors: Option<ColorMap>, // chip-8x's color board
+}
+
+// what the speaker should be doing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sound {
+    pub on: bool,
+    // xo-chip's 1 bit samples replace the beep once a game loads them
+    pub pattern: Option<[u8; 16]>,
+    pub pattern_rate: f32, // samples per second
+}
+
+// a window, terminal or anything else that shows the screen, plays the sound and reads keys.
+// run drives it with the same timing whatever it is
+pub trait Frontend {
+    fn poll_input(&mut self) -> Vec<Input>;
+    fn present(&mut self, screen: &Screen);
+    fn play(&mut self, sound: &Sound);
+    // the game ended and its screen is final, until it's reset
+    fn show_ended(&mut self, _ended: bool) {}
+    // the platform whose quirks the game runs with, none for a mix of them
+    fn show_quirks(&mut self, _quirks: Option<Platform>) {}
+}
+
+// what window titles say besides the name, whose quirks are in use and whether the game ended
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Title {
+    pub quirks: Option<Platform>,
+    pub ended: bool,
+}
+impl fmt::Display for Title {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "chip8")?;
+        if let Some(platform) = self.quirks {
+            write!(f, " - {platform} quirks")?;
+        }
+        if self.ended {
+            write!(f, " - program ended")?;
+        }
+        Ok(())
+    }
+}
+
+// called with the frame's number before each simulated frame runs, after the player's input,
+// e.g. for scripts
+pub type FrameHook = Box<dyn FnMut(&mut Chip8, u64) + Send>;
+
+// how run paces and records the emulation, the machine's own options are set on the machine
+pub struct RunOptions {
+    // simulated frames per second, presentation stays at 60
+    pub sim_hz: f64,
+    // the shortest beep, so sound timers of a frame or two are still heard
+    pub min_beep: Duration,
+    // one "frame checksum" line per simulated frame, for diffing runs against each other
+    pub checksum_log: Option<Box<dyn Write + Send>>,
+    // where the save state hotkeys save to and load from
+    pub state_path: Option<PathBuf>,
+    pub before_frame: Option<FrameHook>,
+    // commands from other programs, polled once per presented frame
+    #[cfg(unix)]
+    pub control: Option<ControlSocket>,
+    // the state over http, with the same commands, polled once per presented frame
+    #[cfg(feature = "http")]
+    pub http: Option<HttpServer>,
+}
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            sim_hz: FRAME_RATE as f64,
+            min_beep: Duration::from_millis(30),
+            checksum_log: None,
+            state_path: None,
+            before_frame: None,
+            #[cfg(unix)]
+            control: None,
+            #[cfg(feature = "http")]
+            http: None,
+        }
+    }
+}
+
+// runs the machine at 60 frames per second until the player quits, the game exits or it
+// crashes, which is the error
+pub fn run(
+    chip8: &mut Chip8,
+    frontend: &mut (impl Frontend + ?Sized),
+    options: RunOptions,
+) -> Result<(), crate::error::Chip8Error> {
+    let mut runner = Runner::new(options);
+    let mut time_last = Instant::now();
+    while runner.frame(chip8, frontend)? {
+        // wait until next frame
+        let time_now = Instant::now();
+        let until_next_frame =
+            FRAME_TIME.saturating_sub(time_now.saturating_duration_since(time_last));
+        thread::sleep(until_next_frame);
+        time_last = time_now;
+    }
+    Ok(())
+}
+
+// like run, but the machine runs on a thread of its own and the frontend stays on this one,
+// only polling input and presenting the frames that come out. a window waiting for vsync or
+// stalling while it's dragged around doesn't hold up the game, and frames that pile up in
+// the meantime are dropped for the newest one.
+// worker::EmulatorHandle can't stand in here: it only steps when told to and hands back bare
+// frames, for callers keeping their own clock. this is run itself, with its timing, hotkeys,
+// save states and control commands, so the frontend is what gets split across the threads
+pub fn run_threaded(
+    chip8: &mut Chip8,
+    frontend: &mut (impl Frontend + ?Sized),
+    options: RunOptions,
+) -> Result<(), crate::error::Chip8Error> {
+    let (input_tx, inputs) = mpsc::channel();
+    let (outputs, output_rx) = mpsc::channel();
+    thread::scope(|scope| {
+        let emulation = scope.spawn(move || {
+            let mut remote = RemoteFrontend { inputs, outputs };
+            run(chip8, &mut remote, options)
+        });
+
+        // the emulation hangs up once the run is over
+        let mut running = true;
+        while running {
+            for input in frontend.poll_input() {
+                let _ = input_tx.send(input);
+            }
+            // waits a frame at most, so input is still polled when no frames come
+            let mut screen: Option<Screen> = None;
+            let mut output = output_rx.recv_timeout(FRAME_TIME).map_err(|e| match e {
+                RecvTimeoutError::Timeout => TryRecvError::Empty,
+                RecvTimeoutError::Disconnected => TryRecvError::Disconnected,
+            });
+            loop {
+                match output {
+                    Ok(Output::Present(mut next)) => {
+                        // what changed in the dropped frames still has to be drawn
+                        if let Some(dropped) = screen {
+                            next.dirty_rows |= dropped.dirty_rows;
+                            next.collided |= dropped.collided;
+                        }
+                        screen = Some(*next);
+                    }
+                    Ok(Output::Play(sound)) => frontend.play(&sound),
+                    Ok(Output::ShowEnded(ended)) => frontend.show_ended(ended),
+                    Ok(Output::ShowQuirks(quirks)) => frontend.show_quirks(quirks),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        running = false;
+                        break;
+                    }
+                }
+                output = output_rx.try_recv();
+            }
+            if let Some(screen) = screen {
+                frontend.present(&screen);
+            }
+        }
+        emulation
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+// what the emulation thread has the frontend do
+enum Output {
+    Present(Box<Screen>),
+    Play(Sound),
+    ShowEnded(bool),
+    ShowQuirks(Option<Platform>),
+}
+
+// stands in for the frontend on the emulation thread, passing everything on to the real one
+struct RemoteFrontend {
+    inputs: Receiver<Input>,
+    outputs: Sender<Output>,
+}
+impl Frontend for RemoteFrontend {
+    fn poll_input(&mut self) -> Vec<Input> {
+        let mut inputs = Vec::new();
+        loop {
+            match self.inputs.try_recv() {
+                Ok(input) => inputs.push(input),
+                Err(TryRecvError::Empty) => return inputs,
+                // the frontend's thread is gone, e.g. it panicked, so the run ends too
+                Err(TryRecvError::Disconnected) => {
+                    inputs.push(Input::Quit);
+                    return inputs;
+                }
+            }
+        }
+    }
+    fn present(&mut self, screen: &Screen) {
+        let _ = self.outputs.send(Output::Present(Box::new(screen.clone())));
+    }
+    fn play(&mut self, sound: &Sound) {
+        let _ = self.outputs.send(Output::Play(*sound));
+    }
+    fn show_ended(&mut self, ended: bool) {
+        let _ = self.outputs.send(Output::ShowEnded(ended));
+    }
+    fn show_quirks(&mut self, quirks: Option<Platform>) {
+        let _ = self.outputs.send(Output::ShowQuirks(quirks));
+    }
+}
+
+// the timing loop one presented frame at a time, without a clock or sleeping, for frontends
+// that are called back once per frame instead, like a browser's animation frames
+pub struct Runner {
+    options: RunOptions,
+    frame_time: i64, // microseconds
+    frame_number: u64,
+    sim_frames: f64,
+    beep_left: Duration,
+    showing_ended: bool,
+    showing_quirks: Option<Platform>,
+    paused: bool,
+}
+impl Runner {
+    pub fn new(options: RunOptions) -> Self {
+        Self {
+            options,
+            frame_time: 0,
+            frame_number: 0,
+            sim_frames: 0.0,
+            beep_left: Duration::ZERO,
+            showing_ended: false,
+            showing_quirks: None,
+            paused: false,
+        }
+    }
+
+    // runs and presents the next frame, false once the player quit or the game exited
+    pub fn frame(
+        &mut self,
+        chip8: &mut Chip8,
+        frontend: &mut (impl Frontend + ?Sized),
+    ) -> Result<bool, crate::error::Chip8Error> {
+        // a span per presented frame, for profiling the loop
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("frame", number = self.frame_number).entered();
+
+        // commands from other programs take effect before the frame runs
+        #[cfg(unix)]
+        if let Some(control) = &mut self.options.control {
+            for request in control.poll() {
+                let quit = request.command == Command::Quit;
+                let result = self.command(chip8, &request.command);
+                request.reply(result);
+                if quit {
+                    report(chip8);
+                    return Ok(false);
+                }
+            }
+        }
+        #[cfg(feature = "http")]
+        if let Some(server) = &mut self.options.http {
+            for request in server.poll(chip8, self.paused) {
+                let quit = request.command == Command::Quit;
+                let result = self.command(chip8, &request.command);
+                request.reply(result);
+                if quit {
+                    report(chip8);
+                    return Ok(false);
+                }
+            }
+        }
+
+        // run the simulated frames that are due, which can be more or less than one per
+        // presented frame when the simulation rate differs from the display rate. the player
+        // can still quit and reset while paused, the game just doesn't run
+        let due = if self.paused {
+            if !self.input(chip8, frontend) {
+                return Ok(false);
+            }
+            0
+        } else {
+            due_frames(&mut self.sim_frames, self.options.sim_hz)
+        };
+        for _ in 0..due {
+            // get new input
+            if !self.input(chip8, frontend) {
+                return Ok(false);
+            }
+            let options = &mut self.options;
+            if let Some(before_frame) = &mut options.before_frame {
+                before_frame(chip8, self.frame_number);
+            }
+
+            // emulate a frame, the time the last one ran over is taken off this one
+            self.frame_time += FRAME_TIME.as_micros() as i64;
+            let frame = chip8.step_frame(self.frame_time)?;
+            self.frame_time = -frame.overrun;
+
+            // the game asked to exit, a game that ended stays on screen
+            if !matches!(chip8.halt(), None | Some(Halt::Ended)) {
+                report(chip8);
+                return Ok(false);
+            }
+
+            // the tone is caught before the timers advanced, a sound timer of 1 would otherwise
+            // be over before it's checked, and beeps are kept open for long enough to be heard
+            if frame.tone {
+                self.beep_left = self.beep_left.max(options.min_beep);
+            }
+
+            // a log that can't be written to is reported once and dropped, the game goes on
+            if let Some(log) = &mut options.checksum_log {
+                let hash = chip8.screen_hash();
+                if let Err(e) = writeln!(log, "{} {hash:016x}", self.frame_number) {
+                    eprintln!("error: couldn't write the checksum log: {e}");
+                    options.checksum_log = None;
+                }
+            }
+            self.frame_number += 1;
+        }
+
+        // maybe play tone, xo-chip games can replace the beep with a pattern of their own
+        frontend.play(&Sound {
+            on: !self.paused && (chip8.tone() || !self.beep_left.is_zero()),
+            pattern: chip8.audio_pattern().copied(),
+            pattern_rate: chip8.pattern_rate(),
+        });
+        self.beep_left = self.beep_left.saturating_sub(FRAME_TIME);
+
+        report(chip8);
+
+        // tell the player the game is over, resets clear it again
+        let ended = chip8.halt() == Some(Halt::Ended);
+        if ended != self.showing_ended {
+            self.showing_ended = ended;
+            frontend.show_ended(ended);
+        }
+        // and whose quirks it runs with, the player cycles them and loading a game can switch
+        let quirks = chip8.quirk_preset();
+        if quirks != self.showing_quirks {
+            self.showing_quirks = quirks;
+            frontend.show_quirks(quirks);
+        }
+
+        let (width, height) = chip8.dimensions();
+        frontend.present(&Screen {
+            pixels: chip8.pixels(),
+            width,
+            height,
+            color_effect: chip8.color_effect(),
+            plane_priority: chip8.plane_priority(),
+            collided: chip8.take_collision(),
+            dirty_rows: chip8.take_dirty_rows(),
+            delay_timer: chip8.delay_timer(),
+            sound_timer: chip8.sound_timer(),
+            colors: chip8.colors().copied(),
+        });
+        Ok(true)
+    }
+
+    // handles the player's input, false once they quit
+    fn input(&mut self, chip8: &mut Chip8, frontend: &mut (impl Frontend + ?Sized)) -> bool {
+        for input in frontend.poll_input() {
+            match input {
+                Input::Quit => {
+                    // a halting sys call ends the loop before it's reported there
+                    report(chip8);
+                    return false;
+                }
+                Input::WarmReset => chip8.warm_reset(),
+                Input::ColdBoot => chip8.cold_boot(),
+                Input::SaveState => save_state(chip8, &self.options.state_path),
+                Input::LoadState => load_state(chip8, &self.options.state_path),
+                Input::CycleQuirks => {
+                    chip8.cycle_quirks();
+                }
+                Input::KeyDown(key) => chip8.key_down(key),
+                Input::KeyUp(key) => chip8.key_up(key),
+            }
+        }
+        true
+    }
+
+    // what a command found goes in the answer, most find nothing
+    #[cfg(any(unix, feature = "http"))]
+    fn command(&mut self, chip8: &mut Chip8, command: &Command) -> Result<String, String> {
+        match command {
+            Command::Pause => self.paused = true,
+            Command::Resume => self.paused = false,
+            Command::Load(path) => {
+                let game = std::fs::read(path)
+                    .map_err(|e| format!("can't read {}: {e}", path.display()))?;
+                chip8.load(&game).map_err(|e| e.to_string())?;
+                chip8.cold_boot();
+            }
+            Command::SaveState(slot) => {
+                let path = self.slot_path(slot)?;
+                let state = chip8.save_state().map_err(|e| e.to_string())?;
+                std::fs::write(&path, state)
+                    .map_err(|e| format!("can't write {}: {e}", path.display()))?;
+            }
+            Command::LoadState(slot) => {
+                let path = self.slot_path(slot)?;
+                let state = std::fs::read(&path)
+                    .map_err(|e| format!("can't read {}: {e}", path.display()))?;
+                chip8.load_state(&state).map_err(|e| e.to_string())?;
+            }
+            Command::Screenshot(path) => {
+                std::fs::write(path, crate::headless::screen_pbm(chip8))
+                    .map_err(|e| format!("can't write {}: {e}", path.display()))?;
+            }
+            Command::Find(bytes) => {
+                let found: Vec<_> = chip8
+                    .find_pattern(bytes)
+                    .iter()
+                    .map(|addr| format!("0x{addr:03x}"))
+                    .collect();
+                return Ok(found.join(" "));
+            }
+            // the loop ends after answering
+            Command::Quit => {}
+        }
+        Ok(String::new())
+    }
+
+    // slot 1 of game.state is game.1.state. slots are plain names, so commands from other
+    // machines can't write elsewhere
+    #[cfg(any(unix, feature = "http"))]
+    fn slot_path(&self, slot: &str) -> Result<PathBuf, String> {
+        let plain = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+        if !slot.chars().all(plain) {
+            return Err(format!(
+                "bad slot {slot}, slots are letters, digits, - and _"
+            ));
+        }
+        let path = self
+            .options
+            .state_path
+            .as_ref()
+            .ok_or("there's no state path")?;
+        Ok(path.with_extension(format!("{slot}.state")))
+    }
+}
+
+// takes the simulated frames that are due this presented frame out of the accumulator
+fn due_frames(sim_frames: &mut f64, sim_hz: f64) -> u32 {
+    *sim_frames += sim_hz / FRAME_RATE as f64;
+    let due = sim_frames.floor();
+    *sim_frames -= due;
+    due as u32
+}
+
+fn save_state(chip8: &Chip8, path: &Option<PathBuf>) {
+    let Some(path) = path else { return };
+    match chip8.save_state() {
+        Ok(state) => {
+            if let Err(e) = std::fs::write(path, state) {
+                eprintln!("error: can't save state: {e}");
+            }
+        }
+        Err(e) => eprintln!("error: can't save state: {e}"),
+    }
+}
+
+fn load_state(chip8: &mut Chip8, path: &Option<PathBuf>) {
+    let Some(path) = path else { return };
+    match std::fs::read(path) {
+        Ok(state) => {
+            if let Err(e) = chip8.load_state(&state) {
+                eprintln!("error: can't load state: {e}");
+            }
+        }
+        Err(e) => eprintln!("error: can't read {}: {e}", path.display()),
+    }
+}
+
+// prints what the game did that the player should know about
+fn report(chip8: &mut Chip8) {
+    for read in chip8.take_uninit_reads() {
+        eprintln!(
+            "warning: v{:x} read before being written at 0x{:03x}",
+            read.register, read.pc
+        );
+    }
+    // calls are only reported as errors when one halted the game
+    let halted = chip8.halt() == Some(Halt::SysCall);
+    for call in chip8.take_sys_calls() {
+        report_sys_call(call, halted);
+    }
+    for unknown in chip8.take_unknown_opcodes() {
+        eprintln!("warning: {unknown}");
+    }
+}
+
+fn report_sys_call(call: SysCall, halts: bool) {
+    let (level, action) = if halts {
+        ("error", "halting")
+    } else {
+        ("warning", "ignored")
+    };
+    eprintln!(
+        "{level}: machine code call sys 0x{:03x} at 0x{:03x} can't be emulated, {action}",
+        call.addr, call.pc
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    // quits after a number of polls, keeping what it was asked to present
+    struct Scripted {
+        polls_left: usize,
+        presented: Vec<Screen>,
+    }
+    impl Frontend for Scripted {
+        fn poll_input(&mut self) -> Vec<Input> {
+            if self.polls_left == 0 {
+                return vec![Input::Quit];
+            }
+            self.polls_left -= 1;
+            Vec::new()
+        }
+        fn present(&mut self, screen: &Screen) {
+            self.presented.push(screen.clone());
+        }
+        fn play(&mut self, _sound: &Sound) {}
+    }
+
+    // a log the test can still read after the runner owns it
+    #[derive(Clone, Default)]
+    struct SharedLog(Arc<Mutex<Vec<u8>>>);
+    impl Write for SharedLog {
+        fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(bytes)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    // a log on a full disk, counting how often it was written to
+    #[derive(Clone, Default)]
+    struct FullLog(Arc<Mutex<usize>>);
+    impl Write for FullLog {
+        fn write(&mut self, _bytes: &[u8]) -> std::io::Result<usize> {
+            *self.0.lock().unwrap() += 1;
+            Err(std::io::ErrorKind::StorageFull.into())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    // presents a number of frames without sleeping in between
+    fn run_frames(chip8: &mut Chip8, options: RunOptions, frames: usize) -> Scripted {
+        let mut frontend = Scripted {
+            polls_left: usize::MAX,
+            presented: Vec::new(),
+        };
+        let mut runner = Runner::new(options);
+        for _ in 0..frames {
+            assert_eq!(runner.frame(chip8, &mut frontend), Ok(true));
+        }
+        frontend
+    }
+
+    #[test]
+    fn same_session_logs_the_same_checksums() {
+        let session = || {
+            let mut chip8 = Chip8::builder().seed(1).build();
+            chip8.load(include_bytes!("../test/3-corax+.ch8")).unwrap();
+            chip8.cold_boot();
+            let log = SharedLog::default();
+            let options = RunOptions {
+                checksum_log: Some(Box::new(log.clone())),
+                ..RunOptions::default()
+            };
+            run_frames(&mut chip8, options, 30);
+            let bytes = log.0.lock().unwrap().clone();
+            String::from_utf8(bytes).unwrap()
+        };
+        let log = session();
+        assert_eq!(log.lines().count(), 30);
+        assert!(log.starts_with("0 "));
+        assert_eq!(session(), log);
+    }
+
+    #[test]
+    fn unwritable_checksum_log_is_dropped_and_the_game_goes_on() {
+        let mut chip8 = Chip8::new();
+        chip8.load(include_bytes!("../test/3-corax+.ch8")).unwrap();
+        let log = FullLog::default();
+        let options = RunOptions {
+            checksum_log: Some(Box::new(log.clone())),
+            ..RunOptions::default()
+        };
+        let frontend = run_frames(&mut chip8, options, 10);
+        assert_eq!(frontend.presented.len(), 10);
+        assert_eq!(*log.0.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn timers_advance_at_the_simulated_rate() {
+        let delay_after = |sim_hz| {
+            let mut chip8 = Chip8::new();
+            // delay timer = 0xff, then loops forever
+            chip8.load(&[0x60, 0xFF, 0xF0, 0x15, 0x12, 0x04]).unwrap();
+            let options = RunOptions {
+                sim_hz,
+                ..RunOptions::default()
+            };
+            let frontend = run_frames(&mut chip8, options, 60);
+            frontend.presented.last().unwrap().delay_timer
+        };
+        // a second of presented frames is half a second of game time at 30hz and two at 120
+        assert_eq!(delay_after(60.0), 0xFF - 60);
+        assert_eq!(delay_after(30.0), 0xFF - 30);
+        assert_eq!(delay_after(120.0), 0xFF - 120);
+    }
+
+    #[test]
+    fn threaded_run_presents_the_machine_until_quit() {
+        let mut chip8 = Chip8::new();
+        // draws the 0 digit in the top left corner, then loops forever
+        chip8.load(&[0xA0, 0x00, 0xD0, 0x05, 0x12, 0x04]).unwrap();
+        let mut frontend = Scripted {
+            polls_left: 10,
+            presented: Vec::new(),
+        };
+        assert_eq!(
+            run_threaded(&mut chip8, &mut frontend, RunOptions::default()),
+            Ok(())
+        );
+        let screen = frontend.presented.last().expect("no frame was presented");
+        assert_eq!(screen.pixels, chip8.pixels());
+        assert_ne!(screen.pixels[0], 0);
+    }
+
+    #[test]
+    fn due_frames_follow_the_simulated_rate() {
+        let frames_in_a_second = |sim_hz| {
+            let mut sim_frames = 0.0;
+            (0..FRAME_RATE)
+                .map(|_| due_frames(&mut sim_frames, sim_hz))
+                .sum::<u32>()
+        };
+        assert_eq!(frames_in_a_second(60.0), 60);
+        assert_eq!(frames_in_a_second(30.0), 30);
+        assert_eq!(frames_in_a_second(120.0), 120);
+        // half speed runs every other presented frame
+        let mut sim_frames = 0.0;
+        assert_eq!(due_frames(&mut sim_frames, 30.0), 0);
+        assert_eq!(due_frames(&mut sim_frames, 30.0), 1);
+    }
+}
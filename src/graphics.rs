@@ -0,0 +1,65 @@
+use crate::chip8::Chip8;
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*, primitives::Rectangle};
+
+// the screen as an embedded-graphics drawable, so the machine can drive a display like an
+// ssd1306 or st7789 directly. each pixel is lit or unlit, whatever planes are lit there
+#[derive(Clone, Copy)]
+pub struct ScreenImage<'a, C> {
+    chip8: &'a Chip8,
+    top_left: Point,
+    lit: C,
+    unlit: C,
+}
+impl<'a> ScreenImage<'a, BinaryColor> {
+    // lit pixels are on, for monochrome displays
+    pub fn new(chip8: &'a Chip8) -> Self {
+        Self::with_colors(chip8, BinaryColor::On, BinaryColor::Off)
+    }
+}
+impl<'a, C: PixelColor> ScreenImage<'a, C> {
+    pub fn with_colors(chip8: &'a Chip8, lit: C, unlit: C) -> Self {
+        Self {
+            chip8,
+            top_left: Point::zero(),
+            lit,
+            unlit,
+        }
+    }
+
+    // every pixel of the screen, row by row
+    pub fn colors(&self) -> impl Iterator<Item = C> + '_ {
+        let chip8 = self.chip8;
+        let (lit, unlit) = (self.lit, self.unlit);
+        let (width, height) = chip8.dimensions();
+        (0..height).flat_map(move |y| {
+            (0..width).map(move |x| if chip8.pixel(x, y) != 0 { lit } else { unlit })
+        })
+    }
+}
+impl<C: PixelColor> OriginDimensions for ScreenImage<'_, C> {
+    fn size(&self) -> Size {
+        let (width, height) = self.chip8.dimensions();
+        Size::new(width as u32, height as u32)
+    }
+}
+impl<C: PixelColor> Transform for ScreenImage<'_, C> {
+    fn translate(&self, by: Point) -> Self {
+        Self {
+            top_left: self.top_left + by,
+            ..*self
+        }
+    }
+    fn translate_mut(&mut self, by: Point) -> &mut Self {
+        self.top_left += by;
+        self
+    }
+}
+impl<C: PixelColor> Drawable for ScreenImage<'_, C> {
+    type Color = C;
+    type Output = ();
+
+    fn draw<D: DrawTarget<Color = C>>(&self, target: &mut D) -> Result<(), D::Error> {
+        let area = Rectangle::new(self.top_left, OriginDimensions::size(self));
+        target.fill_contiguous(&area, self.colors())
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{chip8::Chip8, error::Chip8Error, frontend::FrameHook};
+use std::str::FromStr;
+
+const FRAME_TIME: i64 = 1_000_000 / 60; // microseconds, as in a window
+
+// how far a headless run goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunLimit {
+    Frames(u64),
+    // instructions, timers still advance every 60th of a second of their cost
+    Cycles(u64),
+}
+impl FromStr for RunLimit {
+    type Err = ();
+
+    // "600" frames or "100000c" cycles
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_suffix('c') {
+            Some(cycles) => cycles.parse().map(RunLimit::Cycles).map_err(|_| ()),
+            None => s.parse().map(RunLimit::Frames).map_err(|_| ()),
+        }
+    }
+}
+
+// runs the machine without a frontend as fast as it goes, for tests and scripts. there's no
+// input but what before_frame does, and it stops early once the game exits or ends
+pub fn run_headless(
+    chip8: &mut Chip8,
+    limit: RunLimit,
+    mut before_frame: Option<FrameHook>,
+) -> Result<(), Chip8Error> {
+    let mut before_frame = |chip8: &mut Chip8, number| {
+        if let Some(before_frame) = &mut before_frame {
+            before_frame(chip8, number);
+        }
+    };
+    match limit {
+        RunLimit::Frames(frames) => {
+            let mut frame_time = 0;
+            for number in 0..frames {
+                if chip8.halt().is_some() {
+                    break;
+                }
+                before_frame(chip8, number);
+                // the time the last frame ran over is taken off this one
+                frame_time += FRAME_TIME;
+                frame_time = -chip8.step_frame(frame_time)?.overrun;
+            }
+        }
+        RunLimit::Cycles(cycles) => {
+            let mut budget = FRAME_TIME;
+            let mut ran = 0;
+            let mut number = 0;
+            before_frame(chip8, number);
+            while ran < cycles && chip8.halt().is_none() {
+                let tick = chip8.tick()?;
+                if tick.opcode.is_some() {
+                    ran += 1;
+                    budget -= tick.cost;
+                } else {
+                    // waiting for vblank, the rest of the frame passes
+                    budget = 0;
+                }
+                if budget <= 0 {
+                    chip8.timers();
+                    budget += FRAME_TIME;
+                    number += 1;
+                    before_frame(chip8, number);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// the screen as a binary portable bitmap, lit pixels black
+pub fn screen_pbm(chip8: &Chip8) -> Vec<u8> {
+    let (width, height) = chip8.dimensions();
+    // rows are whole bytes in both, since the screens are 64 or 128 wide
+    let mut pbm = format!("P4\n{width} {height}\n").into_bytes();
+    pbm.extend(chip8.framebuffer());
+    pbm
+}
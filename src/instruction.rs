@@ -0,0 +1,183 @@
+// the operation of an 8xyN instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AluOp {
+    Load,
+    Or,
+    And,
+    Xor,
+    Add,
+    Sub,
+    Shr,
+    SubN,
+    Shl,
+}
+
+// a decoded opcode, registers are indices into v. comments give the opcode and the cowgod
+// mnemonic, platform specific instructions note where they come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    // 00e0 cls
+    Clear,
+    // 00ee ret
+    Return,
+    // 00cn scd n, super-chip
+    ScrollDown(u8),
+    // 00dn scu n, xo-chip
+    ScrollUp(u8),
+    // 00fb scr, super-chip
+    ScrollRight,
+    // 00fc scl, super-chip
+    ScrollLeft,
+    // 00fd exit, super-chip
+    Exit,
+    // 00fe low, super-chip
+    LowRes,
+    // 00ff high, super-chip
+    HighRes,
+    // 0nnn sys nnn, machine code. 0230 clears the screen in hi-res chip-8 and 02a0 cycles the
+    // background color on chip-8x
+    Sys(u16),
+    // 1nnn jp nnn
+    Jump(u16),
+    // 2nnn call nnn
+    Call(u16),
+    // 3xnn se vx, nn
+    SkipEqual(u8, u8),
+    // 4xnn sne vx, nn
+    SkipNotEqual(u8, u8),
+    // 5xy0 se vx, vy, the vip ignores the last nibble
+    SkipEqualRegisters(u8, u8),
+    // 5xy1 nadd vx, vy, chip-8x
+    AddNibbles(u8, u8),
+    // 5xy2 save vx - vy, xo-chip
+    SaveRange(u8, u8),
+    // 5xy3 load vx - vy, xo-chip
+    LoadRange(u8, u8),
+    // 6xnn ld vx, nn
+    Load(u8, u8),
+    // 7xnn add vx, nn
+    Add(u8, u8),
+    // 8xyN arithmetic, logic and shifts
+    Alu(u8, u8, AluOp),
+    // 9xy0 sne vx, vy
+    SkipNotEqualRegisters(u8, u8),
+    // annn ld i, nnn
+    LoadIndex(u16),
+    // bnnn jp v0, nnn, or jp vx, xnn with the jumping quirk. chip-8x colors the screen with
+    // bxyn instead
+    JumpOffset(u8, u16),
+    // cxnn rnd vx, nn
+    Random(u8, u8),
+    // dxyn drw vx, vy, n
+    Draw(u8, u8, u8),
+    // ex9e skp vx
+    SkipKey(u8),
+    // exa1 sknp vx
+    SkipNotKey(u8),
+    // exf2 skp2 vx, chip-8x's second keypad
+    SkipKey2(u8),
+    // exf5 sknp2 vx, chip-8x
+    SkipNotKey2(u8),
+    // f000 nnnn ld i, long, xo-chip. the address is the next word
+    LoadIndexLong,
+    // f002 audio, xo-chip
+    Audio,
+    // fn01 plane n, xo-chip
+    Plane(u8),
+    // fx07 ld vx, dt
+    LoadDelay(u8),
+    // fx0a ld vx, k
+    WaitKey(u8),
+    // fx15 ld dt, vx
+    SetDelay(u8),
+    // fx18 ld st, vx
+    SetSound(u8),
+    // fx1e add i, vx
+    AddIndex(u8),
+    // fx29 ld f, vx
+    Font(u8),
+    // fx30 ld hf, vx, super-chip
+    LargeFont(u8),
+    // fx3a pitch vx, xo-chip
+    Pitch(u8),
+    // fx33 ld b, vx
+    Bcd(u8),
+    // fx55 ld [i], vx
+    Store(u8),
+    // fx65 ld vx, [i]
+    Restore(u8),
+    // fx75 ld r, vx, super-chip
+    StoreFlags(u8),
+    // fx85 ld vx, r, super-chip
+    RestoreFlags(u8),
+}
+
+// the instruction an opcode stands for on any platform, or the opcode back if it's none.
+// whether the running platform has it is up to the interpreter
+pub fn decode(op: u16) -> Result<Instruction, u16> {
+    use Instruction::*;
+    let (x, y, n) = (
+        ((op & 0x0F00) >> 8) as u8,
+        ((op & 0x00F0) >> 4) as u8,
+        (op & 0x000F) as u8,
+    );
+    let (nn, nnn) = ((op & 0x00FF) as u8, op & 0x0FFF);
+    let instruction = match ((op & 0xF000) >> 12, x, y, n) {
+        (0, 0, 0xE, 0) => Clear,
+        (0, 0, 0xE, 0xE) => Return,
+        (0, 0, 0xC, _) => ScrollDown(n),
+        (0, 0, 0xD, _) => ScrollUp(n),
+        (0, 0, 0xF, 0xB) => ScrollRight,
+        (0, 0, 0xF, 0xC) => ScrollLeft,
+        (0, 0, 0xF, 0xD) => Exit,
+        (0, 0, 0xF, 0xE) => LowRes,
+        (0, 0, 0xF, 0xF) => HighRes,
+        (0, ..) => Sys(nnn),
+        (1, ..) => Jump(nnn),
+        (2, ..) => Call(nnn),
+        (3, ..) => SkipEqual(x, nn),
+        (4, ..) => SkipNotEqual(x, nn),
+        (5, _, _, 1) => AddNibbles(x, y),
+        (5, _, _, 2) => SaveRange(x, y),
+        (5, _, _, 3) => LoadRange(x, y),
+        (5, ..) => SkipEqualRegisters(x, y),
+        (6, ..) => Load(x, nn),
+        (7, ..) => Add(x, nn),
+        (8, _, _, 0) => Alu(x, y, AluOp::Load),
+        (8, _, _, 1) => Alu(x, y, AluOp::Or),
+        (8, _, _, 2) => Alu(x, y, AluOp::And),
+        (8, _, _, 3) => Alu(x, y, AluOp::Xor),
+        (8, _, _, 4) => Alu(x, y, AluOp::Add),
+        (8, _, _, 5) => Alu(x, y, AluOp::Sub),
+        (8, _, _, 6) => Alu(x, y, AluOp::Shr),
+        (8, _, _, 7) => Alu(x, y, AluOp::SubN),
+        (8, _, _, 0xE) => Alu(x, y, AluOp::Shl),
+        (9, _, _, 0) => SkipNotEqualRegisters(x, y),
+        (0xA, ..) => LoadIndex(nnn),
+        (0xB, ..) => JumpOffset(x, nnn),
+        (0xC, ..) => Random(x, nn),
+        (0xD, ..) => Draw(x, y, n),
+        (0xE, _, 9, 0xE) => SkipKey(x),
+        (0xE, _, 0xA, 1) => SkipNotKey(x),
+        (0xE, _, 0xF, 2) => SkipKey2(x),
+        (0xE, _, 0xF, 5) => SkipNotKey2(x),
+        (0xF, 0, 0, 0) => LoadIndexLong,
+        (0xF, 0, 0, 2) => Audio,
+        (0xF, _, 0, 1) => Plane(x),
+        (0xF, _, 0, 7) => LoadDelay(x),
+        (0xF, _, 0, 0xA) => WaitKey(x),
+        (0xF, _, 1, 5) => SetDelay(x),
+        (0xF, _, 1, 8) => SetSound(x),
+        (0xF, _, 1, 0xE) => AddIndex(x),
+        (0xF, _, 2, 9) => Font(x),
+        (0xF, _, 3, 0) => LargeFont(x),
+        (0xF, _, 3, 0xA) => Pitch(x),
+        (0xF, _, 3, 3) => Bcd(x),
+        (0xF, _, 5, 5) => Store(x),
+        (0xF, _, 6, 5) => Restore(x),
+        (0xF, _, 7, 5) => StoreFlags(x),
+        (0xF, _, 8, 5) => RestoreFlags(x),
+        _ => return Err(op),
+    };
+    Ok(instruction)
+}
@@ -0,0 +1,383 @@
+use crate::{
+    instruction::{self, AluOp, Instruction, Instruction::*},
+    platform::Platform,
+    quirks::Quirks,
+    timing::TimingTable,
+};
+use cranelift_codegen::{
+    ir::{condcodes::IntCC, types, AbiParam, InstBuilder, MemFlags, Value},
+    settings::{self, Configurable},
+    Context,
+};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{default_libcall_names, Module};
+use std::{collections::HashMap, mem::ManuallyDrop};
+
+const MAX_BLOCK: usize = 64; // instructions
+const MIN_BLOCK: usize = 2; // instructions, shorter runs aren't worth the call
+
+// takes the registers and i, and returns where the game goes next
+type BlockFn = extern "C" fn(*mut u8, *mut u16) -> u32;
+
+// what blocks are compiled from
+pub(crate) struct Code<'a> {
+    pub mem: &'a [u8],
+    pub quirks: Quirks,
+    pub timing: &'a TimingTable,
+    pub platform: Platform,
+    pub two_page: bool,
+}
+
+// a run of instructions compiled to one native function. it's straight-line register
+// arithmetic, optionally ending in a skip or jump, so nothing else of the machine changes
+pub(crate) struct Block {
+    code: BlockFn,
+    bytes: Vec<u8>, // the memory it was compiled from, starting at its address
+    mask: usize,    // the address mask it was compiled for
+    pub instructions: u32,
+    pub cost: i64,      // microseconds, all of the instructions
+    pub last_cost: i64, // of the last one, which the interpreter would start with any budget left
+}
+impl Block {
+    pub fn run(&self, v: &mut [u8; 16], i: &mut u16) -> u16 {
+        (self.code)(v.as_mut_ptr(), i) as u16
+    }
+    fn matches(&self, mem: &[u8], start: usize) -> bool {
+        self.mask == mem.len() - 1 && mem.get(start..start + self.bytes.len()) == Some(&self.bytes)
+    }
+}
+
+enum Entry {
+    Compiled(Block),
+    // too short to compile, or the game wrote over it, which self-modifying games keep doing
+    Interpret,
+}
+
+// compiles blocks the first time the game gets to them and keeps them by address
+pub(crate) struct Jit {
+    module: ManuallyDrop<JITModule>,
+    ctx: Context,
+    builder: FunctionBuilderContext,
+    blocks: HashMap<u16, Entry>,
+}
+// the compiled code is only reached through the jit, which moves with the machine as a whole
+unsafe impl Send for Jit {}
+impl Jit {
+    pub fn new() -> Result<Self, String> {
+        let mut flags = settings::builder();
+        flags.set("is_pic", "false").map_err(|e| e.to_string())?;
+        flags.set("opt_level", "speed").map_err(|e| e.to_string())?;
+        let isa = cranelift_native::builder()?
+            .finish(settings::Flags::new(flags))
+            .map_err(|e| e.to_string())?;
+        let module = JITModule::new(JITBuilder::with_isa(isa, default_libcall_names()));
+        Ok(Self {
+            ctx: module.make_context(),
+            module: ManuallyDrop::new(module),
+            builder: FunctionBuilderContext::new(),
+            blocks: HashMap::new(),
+        })
+    }
+
+    // the block starting at pc, compiled on the first visit. none means the interpreter runs it
+    pub fn block(&mut self, pc: u16, code: &Code) -> Option<&Block> {
+        let start = pc as usize;
+        if !self.blocks.contains_key(&pc) {
+            let entry = match self.compile(code, start) {
+                Some(block) => Entry::Compiled(block),
+                None => Entry::Interpret,
+            };
+            self.blocks.insert(pc, entry);
+        }
+        let entry = self.blocks.get_mut(&pc)?;
+        if let Entry::Compiled(block) = entry {
+            if !block.matches(code.mem, start) {
+                *entry = Entry::Interpret;
+            }
+        }
+        match entry {
+            Entry::Compiled(block) => Some(block),
+            Entry::Interpret => None,
+        }
+    }
+
+    // the quirks or timing changed, which the blocks were compiled with. the code stays
+    // allocated until the jit is dropped
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+    }
+
+    fn compile(&mut self, code: &Code, start: usize) -> Option<Block> {
+        let mem = code.mem;
+        let mask = mem.len() - 1;
+        let mut ops = Vec::new();
+        let mut addr = start;
+        // a skip depends on the size of the instruction after it
+        let mut lookahead = 0;
+        while ops.len() < MAX_BLOCK && addr + 1 < mem.len() {
+            let op = u16::from_be_bytes([mem[addr], mem[addr + 1]]);
+            let Ok(instruction) = instruction::decode(op) else {
+                break;
+            };
+            match instruction {
+                Load(..) | Add(..) | Alu(..) | LoadIndex(_) | AddIndex(_) => {}
+                SkipEqual(..)
+                | SkipNotEqual(..)
+                | SkipEqualRegisters(..)
+                | SkipNotEqualRegisters(..) => {
+                    if addr + 3 >= mem.len() {
+                        break;
+                    }
+                    lookahead = 2;
+                }
+                // jumps to themselves end the game and hi-res chip-8 boots with a special
+                // jump, the interpreter takes care of both
+                Jump(target) => {
+                    let boot = code.two_page && addr == code.platform.start_addr();
+                    if target as usize == addr || (boot && target == 0x260) {
+                        break;
+                    }
+                }
+                _ => break,
+            }
+            ops.push((op, instruction));
+            addr += 2;
+            if lookahead > 0 || matches!(instruction, Jump(_)) {
+                break;
+            }
+        }
+        if ops.len() < MIN_BLOCK {
+            return None;
+        }
+
+        let function = self.codegen(code, &ops, addr, mask)?;
+        let costs: Vec<i64> = ops.iter().map(|&(op, _)| code.timing.cost(op)).collect();
+        Some(Block {
+            code: function,
+            bytes: mem[start..addr + lookahead].to_vec(),
+            mask,
+            instructions: ops.len() as u32,
+            cost: costs.iter().sum(),
+            last_cost: *costs.last()?,
+        })
+    }
+
+    // end is the address after the last instruction
+    fn codegen(
+        &mut self,
+        code: &Code,
+        ops: &[(u16, Instruction)],
+        end: usize,
+        mask: usize,
+    ) -> Option<BlockFn> {
+        let pointer = self.module.target_config().pointer_type();
+        self.ctx.func.signature = self.module.make_signature();
+        let signature = &mut self.ctx.func.signature;
+        signature.params.push(AbiParam::new(pointer));
+        signature.params.push(AbiParam::new(pointer));
+        signature.returns.push(AbiParam::new(types::I32));
+
+        let mut builder = FunctionBuilder::new(&mut self.ctx.func, &mut self.builder);
+        let entry = builder.create_block();
+        builder.append_block_params_for_function_params(entry);
+        builder.switch_to_block(entry);
+        builder.seal_block(entry);
+        let params = builder.block_params(entry);
+        let mut e = Emitter {
+            v_ptr: params[0],
+            i_ptr: params[1],
+            b: builder,
+            v: [None; 16],
+            v_written: 0,
+            i: None,
+        };
+
+        let fall_through = (end & mask) as i64;
+        let mut next = None;
+        for &(_, instruction) in ops {
+            match instruction {
+                Load(x, nn) => {
+                    let value = e.b.ins().iconst(types::I8, nn as i64);
+                    e.set(x, value);
+                }
+                Add(x, nn) => {
+                    let vx = e.get(x);
+                    let value = e.b.ins().iadd_imm(vx, nn as i64);
+                    e.set(x, value);
+                }
+                Alu(x, y, op) => {
+                    let (res, flag) = e.alu(op, x, y, code.quirks);
+                    // vf is written last so the flag wins when x is 0xF
+                    e.set(x, res);
+                    if let Some(flag) = flag {
+                        e.set(0xF, flag);
+                    }
+                }
+                LoadIndex(addr) => {
+                    let value = e.b.ins().iconst(types::I16, addr as i64);
+                    e.i = Some(value);
+                }
+                AddIndex(x) => {
+                    let vx = e.get(x);
+                    let vx = e.b.ins().uextend(types::I16, vx);
+                    let i = e.get_i();
+                    let sum = e.b.ins().iadd(i, vx);
+                    e.i = Some(e.b.ins().band_imm(sum, mask as i64));
+                }
+                SkipEqual(x, nn) | SkipNotEqual(x, nn) => {
+                    let vx = e.get(x);
+                    let cc = if matches!(instruction, SkipEqual(..)) {
+                        IntCC::Equal
+                    } else {
+                        IntCC::NotEqual
+                    };
+                    let taken = e.b.ins().icmp_imm(cc, vx, nn as i64);
+                    next = Some(e.skip(taken, code, end, mask));
+                }
+                SkipEqualRegisters(x, y) | SkipNotEqualRegisters(x, y) => {
+                    let (vx, vy) = (e.get(x), e.get(y));
+                    let cc = if matches!(instruction, SkipEqualRegisters(..)) {
+                        IntCC::Equal
+                    } else {
+                        IntCC::NotEqual
+                    };
+                    let taken = e.b.ins().icmp(cc, vx, vy);
+                    next = Some(e.skip(taken, code, end, mask));
+                }
+                Jump(target) => next = Some(e.b.ins().iconst(types::I32, target as i64)),
+                _ => unreachable!("compile only takes the instructions above"),
+            }
+        }
+        let next = next.unwrap_or_else(|| e.b.ins().iconst(types::I32, fall_through));
+        e.finish(next);
+
+        let id = self
+            .module
+            .declare_anonymous_function(&self.ctx.func.signature)
+            .ok()?;
+        let defined = self.module.define_function(id, &mut self.ctx);
+        self.module.clear_context(&mut self.ctx);
+        defined.ok()?;
+        self.module.finalize_definitions().ok()?;
+        let function = self.module.get_finalized_function(id);
+        // SAFETY: the function was just compiled with BlockFn's parameters and return value, in
+        // the platform's calling convention, and lives as long as the module
+        Some(unsafe { std::mem::transmute::<*const u8, BlockFn>(function) })
+    }
+}
+impl Drop for Jit {
+    fn drop(&mut self) {
+        // SAFETY: the blocks pointing into the module's memory go with it
+        unsafe { ManuallyDrop::take(&mut self.module).free_memory() };
+    }
+}
+
+// builds a block's function, keeping the registers in values until the end
+struct Emitter<'a> {
+    b: FunctionBuilder<'a>,
+    v_ptr: Value,
+    i_ptr: Value,
+    v: [Option<Value>; 16],
+    v_written: u16, // bitmask
+    i: Option<Value>,
+}
+impl Emitter<'_> {
+    fn get(&mut self, x: u8) -> Value {
+        let x = x as usize;
+        match self.v[x] {
+            Some(value) => value,
+            None => {
+                let value = self
+                    .b
+                    .ins()
+                    .load(types::I8, MemFlags::trusted(), self.v_ptr, x as i32);
+                self.v[x] = Some(value);
+                value
+            }
+        }
+    }
+    fn set(&mut self, x: u8, value: Value) {
+        self.v[x as usize] = Some(value);
+        self.v_written |= 1 << x;
+    }
+    fn get_i(&mut self) -> Value {
+        match self.i {
+            Some(value) => value,
+            None => {
+                let value = self
+                    .b
+                    .ins()
+                    .load(types::I16, MemFlags::trusted(), self.i_ptr, 0);
+                self.i = Some(value);
+                value
+            }
+        }
+    }
+
+    // the same results and flags as the interpreter's alu
+    fn alu(&mut self, op: AluOp, x: u8, y: u8, quirks: Quirks) -> (Value, Option<Value>) {
+        let (vx, vy) = (self.get(x), self.get(y));
+        let ins = self.b.ins();
+        let shifted = if quirks.shifting { vx } else { vy };
+        match op {
+            AluOp::Load => (vy, None),
+            AluOp::Or | AluOp::And | AluOp::Xor => {
+                let res = match op {
+                    AluOp::Or => ins.bor(vx, vy),
+                    AluOp::And => self.b.ins().band(vx, vy),
+                    _ => self.b.ins().bxor(vx, vy),
+                };
+                let reset = quirks.vf_reset.then(|| self.b.ins().iconst(types::I8, 0));
+                (res, reset)
+            }
+            AluOp::Add => {
+                let res = ins.iadd(vx, vy);
+                let carry = self.b.ins().icmp(IntCC::UnsignedLessThan, res, vx);
+                (res, Some(carry))
+            }
+            AluOp::Sub => {
+                let res = ins.isub(vx, vy);
+                let no_borrow = self.b.ins().icmp(IntCC::UnsignedGreaterThanOrEqual, vx, vy);
+                (res, Some(no_borrow))
+            }
+            AluOp::SubN => {
+                let res = ins.isub(vy, vx);
+                let no_borrow = self.b.ins().icmp(IntCC::UnsignedGreaterThanOrEqual, vy, vx);
+                (res, Some(no_borrow))
+            }
+            AluOp::Shr => {
+                let res = ins.ushr_imm(shifted, 1);
+                (res, Some(self.b.ins().band_imm(shifted, 1)))
+            }
+            AluOp::Shl => {
+                let res = ins.ishl_imm(shifted, 1);
+                (res, Some(self.b.ins().ushr_imm(shifted, 7)))
+            }
+        }
+    }
+
+    // the next pc after a skip ending at end, xo-chip skips the whole of a long ld i
+    fn skip(&mut self, taken: Value, code: &Code, end: usize, mask: usize) -> Value {
+        let long = code.platform == Platform::XoChip && code.mem[end..end + 2] == [0xF0, 0x00];
+        let skipped = (end + if long { 4 } else { 2 }) & mask;
+        let skipped = self.b.ins().iconst(types::I32, skipped as i64);
+        let not_skipped = self.b.ins().iconst(types::I32, (end & mask) as i64);
+        self.b.ins().select(taken, skipped, not_skipped)
+    }
+
+    fn finish(mut self, next: Value) {
+        for x in 0..16 {
+            if let Some(value) = self.v[x].filter(|_| self.v_written & (1 << x) != 0) {
+                self.b
+                    .ins()
+                    .store(MemFlags::trusted(), value, self.v_ptr, x as i32);
+            }
+        }
+        if let Some(i) = self.i {
+            self.b.ins().store(MemFlags::trusted(), i, self.i_ptr, 0);
+        }
+        self.b.ins().return_(&[next]);
+        self.b.finalize();
+    }
+}
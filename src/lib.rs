@@ -0,0 +1,85 @@
+// a chip-8 interpreter core with no frontend attached, e.g. for embedding in another gui.
+// Chip8 is the machine, the rest configures it or inspects it while it runs.
+//
+//     let mut chip8 = Chip8::builder().platform(Platform::SuperChip).seed(1).build();
+//     chip8.load(&game)?;
+//     chip8.cold_boot();
+//     // once per 60hz frame, after passing on key presses with key_down and key_up
+//     let frame = chip8.step_frame(1_000_000 / 60)?;
+//     // then draw chip8.pixels() at chip8.dimensions() and beep while frame.tone
+//
+// without the default std feature the core is no_std and only needs an allocator, the
+// frontends, sockets, wall clock timers and the opcode profile are left out.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod audio;
+pub mod builder;
+pub mod chip8;
+#[cfg(feature = "std")]
+pub mod control;
+pub mod disasm;
+pub mod error;
+#[cfg(feature = "minifb")]
+pub mod fb;
+#[cfg(feature = "std")]
+pub mod frontend;
+#[cfg(feature = "embedded-graphics")]
+pub mod graphics;
+#[cfg(feature = "std")]
+pub mod headless;
+pub mod instruction;
+#[cfg(feature = "jit")]
+mod jit;
+pub mod platform;
+#[cfg(feature = "std")]
+pub mod profile;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod quirks;
+#[cfg(feature = "std")]
+pub mod render;
+pub mod rng;
+#[cfg(feature = "rhai")]
+pub mod script;
+#[cfg(feature = "sdl")]
+pub mod sdl;
+#[cfg(feature = "http")]
+pub mod server;
+#[cfg(feature = "pixels")]
+pub mod shader;
+#[cfg(feature = "cpal")]
+pub mod speaker;
+pub mod stack;
+pub mod state;
+#[cfg(feature = "tui")]
+pub mod terminal;
+pub mod timer;
+pub mod timing;
+#[cfg(feature = "web")]
+pub mod web;
+#[cfg(feature = "pixels")]
+pub mod window;
+#[cfg(feature = "std")]
+pub mod worker;
+
+#[cfg(feature = "std")]
+pub use crate::{audio::Beeper, frontend::Frontend, worker::EmulatorHandle};
+pub use crate::{
+    builder::Chip8Builder,
+    chip8::{
+        BoundsPolicy, Chip8, ColorEffect, Event, FrameSummary, Halt, InstructionHook, KeyPriority,
+        MemorySize, OpcodePolicy, PlanePriority, SysCall, SysPolicy, TickResult, UninitRead,
+        UnknownOpcode,
+    },
+    error::Chip8Error,
+    instruction::{decode, Instruction},
+    platform::Platform,
+    quirks::Quirks,
+    rng::VipRng,
+    stack::StackError,
+    state::{StateDiff, StateError},
+    timing::TimingTable,
+};
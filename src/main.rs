@@ -1,204 +1,733 @@
-mod chip8;
-
-use crate::chip8::Chip8;
-use sdl2::{
-    audio::{AudioCallback, AudioSpecDesired},
-    event::Event,
-    keyboard::Keycode,
-    pixels::{Color, PixelFormatEnum},
+#[cfg(unix)]
+use chip8::control::ControlSocket;
+#[cfg(feature = "minifb")]
+use chip8::fb::FbFrontend;
+#[cfg(feature = "rhai")]
+use chip8::script::Script;
+#[cfg(feature = "sdl")]
+use chip8::sdl::SdlFrontend;
+#[cfg(feature = "http")]
+use chip8::server::HttpServer;
+#[cfg(feature = "cpal")]
+use chip8::speaker::CpalBeeper;
+#[cfg(feature = "tui")]
+use chip8::terminal::TerminalFrontend;
+#[cfg(feature = "pixels")]
+use chip8::window::WindowFrontend;
+use chip8::{
+    frontend::{self, RunOptions, FRAME_RATE},
+    headless, BoundsPolicy, Chip8, ColorEffect, Frontend, KeyPriority, MemorySize, OpcodePolicy,
+    PlanePriority, Quirks, SysPolicy, VipRng,
 };
 use std::{
-    env, fs, thread,
-    time::{Duration, Instant},
+    env,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+    process,
+    time::Duration,
+};
+
+// the first of the frontends that are built in
+const DEFAULT_FRONTEND: &str = if cfg!(feature = "sdl") {
+    "sdl"
+} else if cfg!(feature = "pixels") {
+    "pixels"
+} else if cfg!(feature = "minifb") {
+    "minifb"
+} else {
+    "tui"
 };
+const USAGE: &str = "\
+Usage: chip8 [OPTIONS] <GAME_PATH>
 
-// console constants
-const SCALING_FACTOR: u32 = 16; // console pixel : real pixels
-const BACKGROUND_COLOR: Color = Color::RGB(153, 102, 1);
-const PIXEL_COLOR: Color = Color::RGB(255, 204, 1);
-const FRAME_RATE: u32 = 60; // hz
-const FRAME_TIME: Duration = Duration::new(0, 1_000_000_000 / FRAME_RATE);
+Options:
+  --frontend NAME           the window to play in, sdl, pixels without sdl, minifb without a
+                            gpu or tui in the terminal, if built with the feature of the same
+                            name. pixels, minifb and tui need cpal for sound (default sdl)
+  --shader FILE             post-process the pixels window with the wgsl fragment shader in FILE,
+                            e.g. shaders/crt.wgsl
+  --single-thread           run the game on the window's thread, so a stalled window slows it
+                            down, instead of a thread of its own
+  --headless N|Nc           run N frames, or N instructions with a c suffix, without a window and
+                            as fast as possible, e.g. for tests and scripts
+  --dump-screen FILE        write the final screen to FILE as a portable bitmap (pbm)
+  --dump-state FILE         write the final machine state to FILE, as saved with F5
+  --trace LEVEL             print tracing events up to error, warn, info (resets and halts), debug
+                            (frames and state changes) or trace (every instruction), if built
+                            with the tracing feature
+  --script FILE             run the rhai script in FILE, which can change the machine, set
+                            breakpoints and press keys every frame, if built with the rhai
+                            feature
+  --control PATH            listen for commands on the unix socket at PATH, one per line: pause,
+                            resume, load ROM, savestate SLOT, loadstate SLOT, screenshot FILE
+                            or quit
+  --http ADDR               serve the screen, registers and memory over http on ADDR, e.g.
+                            127.0.0.1:8080, with post endpoints for the --control commands, if
+                            built with the http feature
+  --checksum-log FILE       write a screen checksum for every frame to FILE
+  --profile-opcodes         print the time spent per instruction class on exit
+  --jit                     compile runs of arithmetic to native code, experimental, if built
+                            with the jit feature
+  --color-effect EFFECT     none, ghosting[=DECAY], neon[=RADIUS] or invert-on-collision
+  --plane-priority PLANE    the color of xo-chip pixels lit on both planes, blend for their
+                            own or 1 or 2 for that plane's (default blend)
+  --warn-uninit             warn about reads of registers never written since reset
+  --boot-garbage            fill memory outside the game with random bytes on boot
+  --seed N                  seed the random numbers, so every run draws the same ones
+  --vip-rng FILE            draw random numbers with the routine of the vip interpreter in FILE
+                            (512 bytes), from the counter --seed sets or 0
+  --record-rng FILE         save the random numbers drawn by the game to FILE
+  --replay-rng FILE         replay random numbers saved with --record-rng
+  --beep-attack-ms MS       fade-in time of the beep (default 2)
+  --beep-release-ms MS      fade-out time of the beep (default 2)
+  --min-beep-ms MS          shortest beep, so brief sound timer values are audible (default 30)
+  --sim-hz HZ               simulated frames per second above 0, presentation stays at 60 (default 60)
+  --platform NAME           emulate chip8, chip48, schip, xochip, eti660 or chip8x, by default
+                            the one known games were written for or chip8
+  --no-detect               don't switch to the platform known games were written for
+  --font FILE               replace the hex digit sprites with the 80 bytes in FILE
+  --font-addr ADDR          load the fonts at hex address ADDR instead of 0x000
+  --key-priority ORDER      which key fx0a takes when several are pressed, lowest, recent or
+                            queue (default lowest)
+  --memory 4k|8k|64k        memory size, by default the platform's
+  --timing TIMING           instruction timing, vip, schip, fast or a number of instructions
+                            per frame (default the platform's)
+  --stack-depth N|unlimited nested calls before the game crashes, by default the platform's
+                            (12 on chip8, eti660 and chip8x, 16 elsewhere)
+  --sys ignore|halt         what machine code calls (0nnn) do, reported either way (default ignore)
+  --unknown-opcode POLICY   what unknown opcodes do, error quits, skip steps over them and halt
+                            stops the game but keeps the screen (default error)
+  --out-of-bounds POLICY    what memory accesses through i past the end of memory do, wrap to
+                            the start or error (default wrap)
+  --quirk NAME=on|off       toggle an interpreter quirk on top of the platform, can be repeated:
+                              shifting: 8xy6/8xyE shift vx in place instead of vy (default off)
+                              memory: fx55/fx65 increment i by x + 1 instead of leaving it (default on)
+                              memory_by_x: the memory quirk increments i by x instead (default off)
+                              vf_reset: or/and/xor reset vf to 0 (default on)
+                              jumping: bxnn jumps to xnn + vx instead of nnn + v0 (default off)
+                              clipping: clip sprites at the screen edges instead of wrapping (default on)
+                              display_wait: drw waits for the next frame (default on for chip8)
+                              half_scroll: lo-res scrolls move half as far (default on for schip)";
 
 fn main() {
-    // initialize core
-    let mut chip8 = Chip8::new();
-    let game = if let [_, file, ..] = env::args().collect::<Vec<_>>().as_slice() {
-        fs::read(file).unwrap()
-    } else {
-        println!("Usage: chip8 <GAME_PATH>");
+    let mut game_path = None;
+    let mut checksum_log = None;
+    let mut profile_opcodes = false;
+    let mut effect = ColorEffect::None;
+    let mut plane_priority = PlanePriority::Blend;
+    let mut warn_uninit = false;
+    let mut record_rng = None;
+    let mut boot_garbage = false;
+    let mut replay_rng = None;
+    let mut platform = None;
+    let mut detect = true;
+    let mut sys_halts = false;
+    let mut opcode_policy = OpcodePolicy::default();
+    let mut bounds_policy = BoundsPolicy::default();
+    let mut memory_size = None;
+    let mut font = None;
+    let mut font_addr = None;
+    let mut key_priority = KeyPriority::Lowest;
+    let mut quirk_toggles = Vec::new();
+    let mut beep_attack_ms: f32 = 2.0;
+    let mut beep_release_ms: f32 = 2.0;
+    let mut min_beep_ms: u64 = 30;
+    let mut timing = None;
+    let mut stack_depth = None;
+    let mut seed = None;
+    let mut vip_rng = None;
+    let mut sim_hz = FRAME_RATE as f64;
+    let mut frontend_name = DEFAULT_FRONTEND.to_string();
+    let mut shader = None;
+    let mut single_thread = false;
+    let mut headless = None;
+    let mut dump_screen = None;
+    let mut dump_state = None;
+    #[cfg(feature = "rhai")]
+    let mut script = None;
+    #[cfg(unix)]
+    let mut control = None;
+    #[cfg(feature = "http")]
+    let mut http = None;
+    #[cfg(feature = "jit")]
+    let mut jit = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--frontend" => match args.next() {
+                Some(name) if ["sdl", "pixels", "minifb", "tui"].contains(&name.as_str()) => {
+                    frontend_name = name
+                }
+                _ => {
+                    println!("{USAGE}");
+                    return;
+                }
+            },
+            "--shader" => shader = args.next(),
+            "--single-thread" => single_thread = true,
+            "--headless" => match args.next().and_then(|limit| limit.parse().ok()) {
+                Some(limit) => headless = Some(limit),
+                None => {
+                    println!("{USAGE}");
+                    return;
+                }
+            },
+            #[cfg(feature = "tracing")]
+            "--trace" => match args
+                .next()
+                .and_then(|level| level.parse::<tracing::Level>().ok())
+            {
+                Some(level) => tracing_subscriber::fmt()
+                    .with_max_level(level)
+                    .with_writer(std::io::stderr)
+                    .init(),
+                None => {
+                    println!("{USAGE}");
+                    return;
+                }
+            },
+            #[cfg(not(feature = "tracing"))]
+            "--trace" => {
+                eprintln!("error: --trace needs the tracing feature");
+                return;
+            }
+            #[cfg(feature = "rhai")]
+            "--script" => script = args.next(),
+            #[cfg(not(feature = "rhai"))]
+            "--script" => {
+                eprintln!("error: --script needs the rhai feature");
+                return;
+            }
+            #[cfg(unix)]
+            "--control" => control = args.next(),
+            #[cfg(not(unix))]
+            "--control" => {
+                eprintln!("error: --control needs unix sockets");
+                return;
+            }
+            #[cfg(feature = "http")]
+            "--http" => http = args.next(),
+            #[cfg(not(feature = "http"))]
+            "--http" => {
+                eprintln!("error: --http needs the http feature");
+                return;
+            }
+            "--dump-screen" => dump_screen = args.next(),
+            "--dump-state" => dump_state = args.next(),
+            "--checksum-log" => checksum_log = args.next(),
+            "--profile-opcodes" => profile_opcodes = true,
+            #[cfg(feature = "jit")]
+            "--jit" => jit = true,
+            #[cfg(not(feature = "jit"))]
+            "--jit" => {
+                eprintln!("error: --jit needs the jit feature");
+                return;
+            }
+            "--warn-uninit" => warn_uninit = true,
+            "--record-rng" => record_rng = args.next(),
+            "--boot-garbage" => boot_garbage = true,
+            "--replay-rng" => replay_rng = args.next(),
+            "--platform" => match args.next().and_then(|p| p.parse().ok()) {
+                Some(p) => platform = Some(p),
+                None => {
+                    println!("{USAGE}");
+                    return;
+                }
+            },
+            "--no-detect" => detect = false,
+            "--quirk" => quirk_toggles.extend(args.next()),
+            "--font" => font = args.next(),
+            "--vip-rng" => vip_rng = args.next(),
+            "--key-priority" => match args.next().and_then(|p| p.parse().ok()) {
+                Some(p) => key_priority = p,
+                None => {
+                    println!("{USAGE}");
+                    return;
+                }
+            },
+            "--font-addr" => {
+                let addr = args
+                    .next()
+                    .and_then(|addr| u16::from_str_radix(addr.trim_start_matches("0x"), 16).ok());
+                match addr {
+                    Some(addr) => font_addr = Some(addr),
+                    None => {
+                        println!("{USAGE}");
+                        return;
+                    }
+                }
+            }
+            "--memory" => match args.next().and_then(|size| size.parse().ok()) {
+                Some(size) => memory_size = Some(size),
+                None => {
+                    println!("{USAGE}");
+                    return;
+                }
+            },
+            "--sys" => match args.next().as_deref() {
+                Some("ignore") => sys_halts = false,
+                Some("halt") => sys_halts = true,
+                _ => {
+                    println!("{USAGE}");
+                    return;
+                }
+            },
+            "--unknown-opcode" => match args.next().and_then(|p| p.parse().ok()) {
+                Some(policy) => opcode_policy = policy,
+                None => {
+                    println!("{USAGE}");
+                    return;
+                }
+            },
+            "--out-of-bounds" => match args.next().and_then(|p| p.parse().ok()) {
+                Some(policy) => bounds_policy = policy,
+                None => {
+                    println!("{USAGE}");
+                    return;
+                }
+            },
+            // a rate that isn't a positive number would never run a frame, or run forever
+            "--sim-hz" => match args
+                .next()
+                .and_then(|hz| hz.parse().ok())
+                .filter(|hz: &f64| hz.is_finite() && *hz > 0.0)
+            {
+                Some(hz) => sim_hz = hz,
+                None => {
+                    println!("{USAGE}");
+                    return;
+                }
+            },
+            "--beep-attack-ms" | "--beep-release-ms" => {
+                let Some(ms) = args.next().and_then(|ms| ms.parse().ok()) else {
+                    println!("{USAGE}");
+                    return;
+                };
+                if arg == "--beep-attack-ms" {
+                    beep_attack_ms = ms;
+                } else {
+                    beep_release_ms = ms;
+                }
+            }
+            "--seed" => match args.next().and_then(|seed| seed.parse().ok()) {
+                Some(s) => seed = Some(s),
+                None => {
+                    println!("{USAGE}");
+                    return;
+                }
+            },
+            "--timing" => match args.next().and_then(|t| t.parse().ok()) {
+                Some(t) => timing = Some(t),
+                None => {
+                    println!("{USAGE}");
+                    return;
+                }
+            },
+            "--stack-depth" => match args.next().as_deref().and_then(parse_stack_depth) {
+                Some(depth) => stack_depth = Some(depth),
+                None => {
+                    println!("{USAGE}");
+                    return;
+                }
+            },
+            "--min-beep-ms" => match args.next().and_then(|ms| ms.parse().ok()) {
+                Some(ms) => min_beep_ms = ms,
+                None => {
+                    println!("{USAGE}");
+                    return;
+                }
+            },
+            "--color-effect" => match args.next().as_deref().and_then(color_effect) {
+                Some(e) => effect = e,
+                None => {
+                    println!("{USAGE}");
+                    return;
+                }
+            },
+            "--plane-priority" => match args.next().and_then(|p| p.parse().ok()) {
+                Some(p) => plane_priority = p,
+                None => {
+                    println!("{USAGE}");
+                    return;
+                }
+            },
+            _ => game_path = Some(arg),
+        }
+    }
+    let Some(game_path) = game_path else {
+        println!("{USAGE}");
         return;
     };
-    chip8.load(&game);
-    let (screen_width, screen_height) = chip8.dimensions();
-
-    // initialize frontend
-    let ctx = sdl2::init().unwrap();
-    let video = ctx.video().unwrap();
-    let window = video
-        .window(
-            "chip8",
-            screen_width as u32 * SCALING_FACTOR,
-            screen_height as u32 * SCALING_FACTOR,
-        )
-        .opengl()
-        .build()
-        .unwrap();
-    let mut canvas = window.into_canvas().build().unwrap();
-
-    let texture_creator = canvas.texture_creator();
-    let mut texture = texture_creator
-        .create_texture_streaming(
-            PixelFormatEnum::RGB24,
-            screen_width as u32,
-            screen_height as u32,
-        )
-        .unwrap();
-
-    let audio = ctx.audio().unwrap();
-    let spec = AudioSpecDesired {
-        freq: Some(44100),
-        channels: Some(1),
-        samples: None,
+    let game = match fs::read(&game_path) {
+        Ok(game) => game,
+        Err(e) => {
+            eprintln!("error: can't read {game_path}: {e}");
+            return;
+        }
     };
-    let device = audio
-        .open_playback(None, &spec, |spec| SquareWave {
-            phase_inc: 110.0 / spec.freq as f32,
-            phase: 0.0,
-            volume: 0.10,
-        })
-        .unwrap();
+    // rpl flags are kept next to the game, e.g. game.ch8 saves to game.rpl
+    let rpl_path = Path::new(&game_path).with_extension("rpl");
+    // and so is the quick save state, game.ch8 saves to game.state
+    let state_path = Path::new(&game_path).with_extension("state");
 
-    let mut event_pump = ctx.event_pump().unwrap();
-
-    // run forever
-    let mut time_last = Instant::now();
-    let mut frame_time = 0;
-    loop {
-        // emulate a frame
-        frame_time += FRAME_TIME.as_micros() as i64;
-        while frame_time > 0 {
-            // get new input
-            for event in event_pump.poll_iter() {
-                match event {
-                    Event::Quit { .. }
-                    | Event::KeyDown {
-                        keycode: Some(Keycode::Escape),
-                        ..
-                    } => return,
-                    Event::KeyDown {
-                        keycode: Some(keycode),
-                        ..
-                    } => {
-                        if let Some(k) = button(keycode) {
-                            chip8.keypad[k] = true;
-                        }
-                    }
-                    Event::KeyUp {
-                        keycode: Some(keycode),
-                        ..
-                    } => {
-                        if let Some(k) = button(keycode) {
-                            chip8.keypad[k] = false;
-                        }
-                    }
-                    _ => {}
-                }
+    // the toggles are applied once the game is loaded, but a typo shouldn't get that far
+    let mut quirks = Quirks::default();
+    if quirk_toggles
+        .iter()
+        .any(|toggle| set_quirk(&mut quirks, toggle).is_none())
+    {
+        println!("{USAGE}");
+        return;
+    }
+    let sys_policy = if sys_halts {
+        SysPolicy::Halt
+    } else {
+        SysPolicy::Ignore
+    };
+    // initialize core, a given platform overrides the one detected for known games when they're
+    // loaded
+    let mut builder = Chip8::builder()
+        .platform(platform.unwrap_or_default())
+        .detect_platform(platform.is_none() && detect)
+        .key_priority(key_priority)
+        .sys_policy(sys_policy)
+        .opcode_policy(opcode_policy)
+        .bounds_policy(bounds_policy)
+        .boot_garbage(boot_garbage)
+        .color_effect(effect)
+        .plane_priority(plane_priority)
+        .warn_uninit(warn_uninit)
+        .opcode_profile(profile_opcodes);
+    if let Some(path) = font {
+        let font = match fs::read(&path) {
+            Ok(font) => font,
+            Err(e) => {
+                eprintln!("error: can't read {path}: {e}");
+                process::exit(1);
             }
+        };
+        let Ok(font) = font.try_into() else {
+            eprintln!("error: {path} must be 80 bytes, 5 for each of the 16 hex digits");
+            process::exit(1);
+        };
+        builder = builder.font(font);
+    }
+    if let Some(addr) = font_addr {
+        builder = builder.font_addr(addr);
+    }
+    if let Some(memory_size) = memory_size {
+        builder = builder.memory_size(memory_size);
+    }
+    if let Some(path) = vip_rng {
+        let image = match fs::read(&path) {
+            Ok(image) => image,
+            Err(e) => {
+                eprintln!("error: can't read {path}: {e}");
+                process::exit(1);
+            }
+        };
+        let Some(rng) = VipRng::from_interpreter(&image, seed.unwrap_or(0) as u16) else {
+            eprintln!("error: {path} must be the 512 bytes of the vip's interpreter");
+            process::exit(1);
+        };
+        builder = builder.rng(rng);
+    } else if let Some(seed) = seed {
+        builder = builder.seed(seed);
+    }
+    let mut chip8 = builder.build();
+    #[cfg(feature = "jit")]
+    if jit {
+        if let Err(e) = chip8.enable_jit() {
+            eprintln!("error: can't start the jit: {e}");
+            return;
+        }
+    }
+    if let Err(e) = chip8.load(&game) {
+        eprintln!("error: can't load {game_path}: {e}");
+        if chip8.memory().len() < MemorySize::Kb64.bytes() {
+            eprintln!("larger games need more memory, e.g. --memory 64k or --platform xochip");
+        }
+        return;
+    }
+    // the memory size, timing, stack depth and individual quirks override the platform's
+    let mut quirks = chip8.quirks();
+    for toggle in &quirk_toggles {
+        set_quirk(&mut quirks, toggle);
+    }
+    chip8.set_quirks(quirks);
+    if let Some(timing) = timing {
+        chip8.set_timing(timing);
+    }
+    if let Some(depth) = stack_depth {
+        chip8.set_stack_depth(depth);
+    }
+    chip8.cold_boot();
+    if let Ok(flags) = fs::read(&rpl_path) {
+        chip8.set_rpl_flags(&flags);
+    }
+    if let Some(path) = &replay_rng {
+        match fs::read(path) {
+            Ok(draws) => chip8.replay_rng(draws),
+            Err(e) => {
+                eprintln!("error: can't read {path}: {e}");
+                process::exit(1);
+            }
+        }
+    } else if record_rng.is_some() {
+        chip8.record_rng();
+    }
 
-            // tick core
-            let tick_time = chip8.tick();
+    // one "frame checksum" line per frame, for diffing runs against each other
+    let checksum_log = match checksum_log.map(|path| File::create(&path).map_err(|e| (path, e))) {
+        Some(Err((path, e))) => {
+            eprintln!("error: can't create {path}: {e}");
+            process::exit(1);
+        }
+        Some(Ok(file)) => Some(Box::new(BufWriter::new(file)) as Box<dyn Write + Send>),
+        None => None,
+    };
+    #[allow(unused_mut)] // without rhai or unix sockets nothing is added
+    let mut options = RunOptions {
+        sim_hz,
+        min_beep: Duration::from_millis(min_beep_ms),
+        checksum_log,
+        state_path: Some(state_path),
+        before_frame: None,
+        #[cfg(unix)]
+        control: None,
+        #[cfg(feature = "http")]
+        http: None,
+    };
+    #[cfg(unix)]
+    if let Some(path) = control {
+        match ControlSocket::bind(&path) {
+            Ok(socket) => options.control = Some(socket),
+            Err(e) => {
+                eprintln!("error: can't listen on {path}: {e}");
+                return;
+            }
+        }
+    }
+    #[cfg(feature = "http")]
+    if let Some(addr) = http {
+        match HttpServer::bind(&addr) {
+            Ok(server) => options.http = Some(server),
+            Err(e) => {
+                eprintln!("error: can't listen on {addr}: {e}");
+                return;
+            }
+        }
+    }
+    #[cfg(feature = "rhai")]
+    if let Some(path) = script {
+        let source = match fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("error: can't read {path}: {e}");
+                return;
+            }
+        };
+        let mut script = match Script::new(&source, &mut chip8) {
+            Ok(script) => script,
+            Err(e) => {
+                eprintln!("error: script {path}: {e}");
+                return;
+            }
+        };
+        options.before_frame = Some(Box::new(move |chip8, number| {
+            if let Err(e) = script.frame(chip8, number) {
+                eprintln!("error: script: {e}");
+            }
+        }));
+    }
 
-            frame_time -= tick_time;
+    if let Some(limit) = headless {
+        if let Err(e) = headless::run_headless(&mut chip8, limit, options.before_frame) {
+            eprintln!("error: {e}");
         }
+    } else {
+        let shader = match shader.map(|path| fs::read_to_string(&path).map_err(|e| (path, e))) {
+            Some(Err((path, e))) => {
+                eprintln!("error: can't read {path}: {e}");
+                return;
+            }
+            Some(Ok(source)) => Some(source),
+            None => None,
+        };
 
-        // advance timers and maybe play tone
-        chip8.timers();
-        if chip8.tone() {
-            device.resume()
+        // initialize frontend and run until the window is closed
+        let frontend = open_frontend(
+            &frontend_name,
+            chip8.dimensions(),
+            beep_attack_ms,
+            beep_release_ms,
+            shader.as_deref(),
+        );
+        let mut frontend = match frontend {
+            Ok(frontend) => frontend,
+            Err(e) => {
+                eprintln!("error: can't open the window: {e}");
+                return;
+            }
+        };
+        let result = if single_thread {
+            frontend::run(&mut chip8, frontend.as_mut(), options)
         } else {
-            device.pause()
+            frontend::run_threaded(&mut chip8, frontend.as_mut(), options)
+        };
+        // closed before printing, the terminal frontend gives the terminal back then
+        drop(frontend);
+        if let Err(e) = result {
+            eprintln!("error: {e}");
         }
+    }
 
-        // present the frame buffer
-        // draw on the texture
-        let _ = texture.with_lock(None, |pixels: &mut [u8], pitch: usize| {
-            for i in (0..(pitch * screen_height)).step_by(3) {
-                // fade existing pixels to black to simulate display fading
-                pixels[i] = lerp(pixels[i], BACKGROUND_COLOR.r, 0.3, 5);
-                pixels[i + 1] = lerp(pixels[i + 1], BACKGROUND_COLOR.g, 0.3, 5);
-                pixels[i + 2] = lerp(pixels[i + 2], BACKGROUND_COLOR.b, 0.3, 5);
-
-                // draw new pixels
-                if chip8.screen[i / 3] {
-                    pixels[i] = PIXEL_COLOR.r;
-                    pixels[i + 1] = PIXEL_COLOR.g;
-                    pixels[i + 2] = PIXEL_COLOR.b;
+    // what was recorded or dumped is written out even if some of it fails
+    let mut write_failed = false;
+    if let Some(path) = dump_screen {
+        if let Err(e) = fs::write(&path, headless::screen_pbm(&chip8)) {
+            eprintln!("error: can't write {path}: {e}");
+            write_failed = true;
+        }
+    }
+    if let Some(path) = dump_state {
+        match chip8.save_state() {
+            Ok(state) => {
+                if let Err(e) = fs::write(&path, state) {
+                    eprintln!("error: can't write {path}: {e}");
+                    write_failed = true;
                 }
             }
-        });
+            Err(e) => {
+                eprintln!("error: can't save state: {e}");
+                write_failed = true;
+            }
+        }
+    }
+    if let Some(profile) = chip8.opcode_profile() {
+        print!("{profile}");
+    }
+    if let Some(path) = record_rng {
+        if let Err(e) = fs::write(&path, chip8.recorded_rng()) {
+            eprintln!("error: can't write {path}: {e}");
+            write_failed = true;
+        }
+    }
+    // only games that use the flags get a file
+    if chip8.rpl_flags().iter().any(|&flag| flag != 0) || rpl_path.exists() {
+        if let Err(e) = fs::write(&rpl_path, chip8.rpl_flags()) {
+            eprintln!(
+                "warning: can't save the flags to {}: {e}",
+                rpl_path.display()
+            );
+        }
+    }
+    if write_failed {
+        process::exit(1);
+    }
+}
 
-        // present the texture
-        canvas.set_draw_color(BACKGROUND_COLOR);
-        canvas.clear();
-        canvas.copy(&texture, None, None).unwrap();
-        canvas.present();
+// the beep fades in and out over the attack and release times, where the frontend has sound,
+// and the shader is only run by the pixels window
+#[allow(unused_variables)] // not every frontend is built in
+fn open_frontend(
+    name: &str,
+    size: (usize, usize),
+    beep_attack_ms: f32,
+    beep_release_ms: f32,
+    shader: Option<&str>,
+) -> Result<Box<dyn Frontend>, String> {
+    match name {
+        #[cfg(feature = "sdl")]
+        "sdl" if shader.is_some() => Err("shaders need --frontend pixels".to_string()),
+        #[cfg(feature = "sdl")]
+        "sdl" => Ok(Box::new(SdlFrontend::new(
+            size,
+            beep_attack_ms,
+            beep_release_ms,
+        )?)),
+        #[cfg(feature = "pixels")]
+        "pixels" => {
+            let mut window = WindowFrontend::new(size, speaker(beep_attack_ms, beep_release_ms))?;
+            if let Some(source) = shader {
+                window = window.with_shader(source)?;
+            }
+            Ok(Box::new(window))
+        }
+        #[cfg(feature = "minifb")]
+        "minifb" if shader.is_some() => Err("shaders need --frontend pixels".to_string()),
+        #[cfg(feature = "minifb")]
+        "minifb" => Ok(Box::new(FbFrontend::new(
+            size,
+            speaker(beep_attack_ms, beep_release_ms),
+        )?)),
+        #[cfg(feature = "tui")]
+        "tui" if shader.is_some() => Err("shaders need --frontend pixels".to_string()),
+        #[cfg(feature = "tui")]
+        "tui" => Ok(Box::new(TerminalFrontend::new(speaker(
+            beep_attack_ms,
+            beep_release_ms,
+        ))?)),
+        _ => Err(format!("this build has no {name} frontend")),
+    }
+}
 
-        // wait until next frame
-        let time_now = Instant::now();
-        let until_next_frame =
-            FRAME_TIME.saturating_sub(time_now.saturating_duration_since(time_last));
-        thread::sleep(until_next_frame);
-        time_last = time_now;
+// sound for the frontends without their own, if built with cpal
+#[cfg(any(feature = "pixels", feature = "minifb", feature = "tui"))]
+#[allow(unused_variables)]
+fn speaker(beep_attack_ms: f32, beep_release_ms: f32) -> Option<Box<dyn chip8::Beeper>> {
+    #[cfg(feature = "cpal")]
+    match CpalBeeper::new(beep_attack_ms, beep_release_ms) {
+        Ok(beeper) => return Some(Box::new(beeper)),
+        Err(e) => eprintln!("warning: no sound: {e}"),
     }
+    None
 }
 
-fn button(keycode: Keycode) -> Option<usize> {
-    let index = match keycode {
-        Keycode::Num1 => 0x1,
-        Keycode::Num2 => 0x2,
-        Keycode::Num3 => 0x3,
-        Keycode::Num4 => 0xC,
-        Keycode::Q => 0x4,
-        Keycode::W => 0x5,
-        Keycode::E => 0x6,
-        Keycode::R => 0xD,
-        Keycode::A => 0x7,
-        Keycode::S => 0x8,
-        Keycode::D => 0x9,
-        Keycode::F => 0xE,
-        Keycode::Z => 0xA,
-        Keycode::X => 0x0,
-        Keycode::C => 0xB,
-        Keycode::V => 0xF,
+// applies a "NAME=on|off" quirk toggle
+fn set_quirk(quirks: &mut Quirks, arg: &str) -> Option<()> {
+    let (name, value) = arg.split_once('=')?;
+    let quirk = match name {
+        "shifting" => &mut quirks.shifting,
+        "memory" => &mut quirks.memory,
+        "memory_by_x" => &mut quirks.memory_by_x,
+        "vf_reset" => &mut quirks.vf_reset,
+        "jumping" => &mut quirks.jumping,
+        "clipping" => &mut quirks.clipping,
+        "display_wait" => &mut quirks.display_wait,
+        "half_scroll" => &mut quirks.half_scroll,
         _ => return None,
     };
-    Some(index)
-}
-
-struct SquareWave {
-    phase_inc: f32,
-    phase: f32,
-    volume: f32,
+    *quirk = match value {
+        "on" => true,
+        "off" => false,
+        _ => return None,
+    };
+    Some(())
 }
-impl AudioCallback for SquareWave {
-    type Channel = f32;
 
-    fn callback(&mut self, out: &mut [Self::Channel]) {
-        for x in out.iter_mut() {
-            *x = if self.phase <= 0.5 {
-                self.volume
-            } else {
-                -self.volume
-            };
-            self.phase = (self.phase + self.phase_inc) % 1.0;
-        }
+// parses a number of nested calls or "unlimited"
+fn parse_stack_depth(arg: &str) -> Option<Option<usize>> {
+    match arg {
+        "unlimited" => Some(None),
+        depth => depth.parse().ok().map(Some),
     }
 }
 
-fn lerp(start: u8, end: u8, t: f32, min: u8) -> u8 {
-    if start.abs_diff(end) < min {
-        end
-    } else {
-        (start as f32 + (end as f32 - start as f32) * t) as u8
-    }
+// parses "none", "ghosting[=DECAY]", "neon[=RADIUS]" or "invert-on-collision"
+fn color_effect(arg: &str) -> Option<ColorEffect> {
+    let (name, param) = arg.split_once('=').unwrap_or((arg, ""));
+    let effect = match name {
+        "none" => ColorEffect::None,
+        "ghosting" => ColorEffect::Ghosting {
+            decay: param.parse().unwrap_or(0.1),
+        },
+        "neon" => ColorEffect::Neon {
+            bloom_radius: param.parse().unwrap_or(2),
+        },
+        "invert-on-collision" => ColorEffect::InvertOnCollision,
+        _ => return None,
+    };
+    Some(effect)
 }
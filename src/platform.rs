@@ -0,0 +1,198 @@
+use crate::{chip8::MemorySize, instruction::Instruction, quirks::Quirks, timing::TimingTable};
+use core::{fmt, str::FromStr};
+
+// the database of known games, see the file for its format
+const KNOWN_ROMS: &str = include_str!("roms.txt");
+
+// interpreters games were written for, each with its own set of quirks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Platform {
+    // the original cosmac vip interpreter
+    #[default]
+    Chip8,
+    // chip-48 on the hp48 calculators
+    Chip48,
+    // super-chip 1.1 on the hp48 calculators
+    SuperChip,
+    // octo's xo-chip
+    XoChip,
+    // the eti-660 kit computer, with a vip-like interpreter but a shorter screen
+    Eti660,
+    // chip-8x on the vip with rca's vp-590 color board and a second keypad
+    Chip8X,
+}
+impl Platform {
+    // the platforms with quirks of their own, in the order Chip8::cycle_quirks goes through them
+    pub const QUIRK_PRESETS: [Platform; 4] = [
+        Platform::Chip8,
+        Platform::Chip48,
+        Platform::SuperChip,
+        Platform::XoChip,
+    ];
+
+    // looks the game up in the database of known roms
+    pub fn detect(rom: &[u8]) -> Option<Self> {
+        let hash = rom_hash(rom);
+        known_roms()
+            .find(|&(known, _)| known == hash)
+            .map(|(_, platform)| platform)
+    }
+    // whether the platform's interpreter has the instruction, the ones it doesn't have are unknown
+    // opcodes there
+    pub fn supports(self, instruction: Instruction) -> bool {
+        use Instruction::*;
+        match instruction {
+            ScrollDown(_) | ScrollRight | ScrollLeft | Exit | LowRes | HighRes | LargeFont(_)
+            | StoreFlags(_) | RestoreFlags(_) => matches!(self, Self::SuperChip | Self::XoChip),
+            ScrollUp(_) | SaveRange(..) | LoadRange(..) | LoadIndexLong | Audio | Plane(_)
+            | Pitch(_) => self == Self::XoChip,
+            AddNibbles(..) | SkipKey2(_) | SkipNotKey2(_) => self == Self::Chip8X,
+            _ => true,
+        }
+    }
+    pub fn quirks(self) -> Quirks {
+        match self {
+            Platform::Chip8 | Platform::Eti660 | Platform::Chip8X => Quirks {
+                vf_reset: true,
+                memory: true,
+                memory_by_x: false,
+                shifting: false,
+                jumping: false,
+                clipping: true,
+                display_wait: true,
+                half_scroll: false,
+            },
+            Platform::Chip48 => Quirks {
+                vf_reset: false,
+                memory: true,
+                memory_by_x: true,
+                shifting: true,
+                jumping: true,
+                clipping: true,
+                display_wait: false,
+                half_scroll: false,
+            },
+            Platform::SuperChip => Quirks {
+                vf_reset: false,
+                memory: false,
+                memory_by_x: false,
+                shifting: true,
+                jumping: true,
+                clipping: true,
+                display_wait: false,
+                half_scroll: true,
+            },
+            Platform::XoChip => Quirks {
+                vf_reset: false,
+                memory: true,
+                memory_by_x: false,
+                shifting: false,
+                jumping: false,
+                clipping: false,
+                display_wait: false,
+                half_scroll: false,
+            },
+        }
+    }
+    // where games are loaded and execution starts, everything below is reserved
+    pub fn start_addr(self) -> usize {
+        match self {
+            Platform::Eti660 => 0x0600,
+            // the chip-8x interpreter is a page longer
+            Platform::Chip8X => 0x0300,
+            _ => 0x0200,
+        }
+    }
+    // xo-chip extends the address space to 16 bits
+    pub fn memory_size(self) -> MemorySize {
+        match self {
+            Platform::XoChip => MemorySize::Kb64,
+            _ => MemorySize::Kb4,
+        }
+    }
+    pub fn timing(self) -> TimingTable {
+        match self {
+            Platform::Chip8 | Platform::Eti660 | Platform::Chip8X => TimingTable::vip(),
+            Platform::Chip48 | Platform::SuperChip => TimingTable::super_chip(),
+            // octo runs 1000 instructions per frame by default
+            Platform::XoChip => TimingTable::per_frame(1000),
+        }
+    }
+    // nested calls the interpreter has room for, the vip only reserves 12 return addresses
+    pub fn stack_depth(self) -> Option<usize> {
+        match self {
+            Platform::Chip8 | Platform::Eti660 | Platform::Chip8X => Some(12),
+            _ => Some(16),
+        }
+    }
+    // screen height outside of hi-res modes, in pixels
+    pub fn lores_height(self) -> usize {
+        match self {
+            Platform::Eti660 => 48,
+            _ => 32,
+        }
+    }
+}
+// the same names from_str takes
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Platform::Chip8 => "chip8",
+            Platform::Chip48 => "chip48",
+            Platform::SuperChip => "schip",
+            Platform::XoChip => "xochip",
+            Platform::Eti660 => "eti660",
+            Platform::Chip8X => "chip8x",
+        };
+        f.write_str(name)
+    }
+}
+impl FromStr for Platform {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "chip8" => Ok(Platform::Chip8),
+            "chip48" => Ok(Platform::Chip48),
+            "schip" => Ok(Platform::SuperChip),
+            "xochip" => Ok(Platform::XoChip),
+            "eti660" => Ok(Platform::Eti660),
+            "chip8x" => Ok(Platform::Chip8X),
+            _ => Err(()),
+        }
+    }
+}
+
+// the hash and platform on each line of the database, lines that don't parse are skipped
+fn known_roms() -> impl Iterator<Item = (u64, Platform)> {
+    KNOWN_ROMS
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let hash = u64::from_str_radix(fields.next()?, 16).ok()?;
+            let platform = fields.next()?.parse().ok()?;
+            Some((hash, platform))
+        })
+}
+
+fn rom_hash(rom: &[u8]) -> u64 {
+    rom.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_known_rom_is_detected() {
+        let entries = KNOWN_ROMS.lines().filter(|line| !line.starts_with('#'));
+        assert_eq!(entries.count(), known_roms().count());
+        let scrolling = include_bytes!("../test/8-scrolling.ch8");
+        assert_eq!(Platform::detect(scrolling), Some(Platform::SuperChip));
+        assert_eq!(Platform::detect(&[0x12, 0x00]), None);
+    }
+}
@@ -0,0 +1,49 @@
+use crate::disasm;
+use std::{collections::HashMap, fmt, time::Duration};
+
+// wall time spent executing each instruction class
+#[derive(Default)]
+pub struct OpcodeProfile {
+    samples: HashMap<&'static str, (Duration, u64)>,
+}
+impl OpcodeProfile {
+    pub fn record(&mut self, op: u16, elapsed: Duration) {
+        let (time, count) = self.samples.entry(disasm::mnemonic(op)).or_default();
+        *time += elapsed;
+        *count += 1;
+    }
+    pub fn time(&self, mnemonic: &str) -> Duration {
+        self.samples
+            .get(mnemonic)
+            .map_or(Duration::ZERO, |&(time, _)| time)
+    }
+    pub fn total(&self) -> Duration {
+        self.samples.values().map(|&(time, _)| time).sum()
+    }
+}
+impl fmt::Display for OpcodeProfile {
+    // one row per instruction class, most expensive first
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut rows = self.samples.iter().collect::<Vec<_>>();
+        rows.sort_by(|(_, (a, _)), (_, (b, _))| b.cmp(a));
+        let total = self.total().as_secs_f64().max(f64::EPSILON);
+
+        writeln!(
+            f,
+            "{:<16}{:>12}{:>12}{:>10}{:>8}",
+            "instruction", "count", "total ms", "avg ns", "%"
+        )?;
+        for (mnemonic, (time, count)) in rows {
+            writeln!(
+                f,
+                "{:<16}{:>12}{:>12.3}{:>10}{:>8.1}",
+                mnemonic,
+                count,
+                time.as_secs_f64() * 1000.0,
+                time.as_nanos() / *count as u128,
+                time.as_secs_f64() / total * 100.0
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+#![allow(clippy::useless_conversion)] // pyo3's macros convert every error into a PyErr
+
+use crate::{chip8::Chip8, platform::Platform};
+use pyo3::{
+    exceptions::{PyRuntimeError, PyValueError},
+    prelude::*,
+    types::PyBytes,
+};
+
+const FRAME_TIME: i64 = 1_000_000 / 60; // microseconds, as in a window
+
+// the machine for python, e.g. for notebooks analysing games:
+//
+//     import chip8
+//     machine = chip8.Chip8("schip")
+//     machine.load(open("game.ch8", "rb").read())
+//     machine.step_frame()
+//     machine.framebuffer()
+#[pyclass(name = "Chip8", module = "chip8", unsendable)]
+pub struct PyChip8 {
+    inner: Chip8,
+}
+#[pymethods]
+impl PyChip8 {
+    // chip8, chip48, schip, xochip, eti660 or chip8x, none for the one known games were written for
+    #[new]
+    #[pyo3(signature = (platform = None))]
+    fn new(platform: Option<&str>) -> PyResult<Self> {
+        let mut builder = Chip8::builder();
+        if let Some(name) = platform {
+            let platform = name
+                .parse::<Platform>()
+                .map_err(|_| PyValueError::new_err(format!("unknown platform {name}")))?;
+            builder = builder.platform(platform);
+        }
+        Ok(Self {
+            inner: builder.build(),
+        })
+    }
+
+    // loads the game and boots it
+    fn load(&mut self, game: &[u8]) -> PyResult<()> {
+        self.inner.load(game).map_err(error)?;
+        self.inner.cold_boot();
+        Ok(())
+    }
+    fn cold_boot(&mut self) {
+        self.inner.cold_boot();
+    }
+    fn warm_reset(&mut self) {
+        self.inner.warm_reset();
+    }
+
+    // runs one instruction, (pc, opcode, cost in microseconds, display changed). the opcode is
+    // None when nothing ran
+    fn tick(&mut self) -> PyResult<(u16, Option<u16>, i64, bool)> {
+        let tick = self.inner.tick().map_err(error)?;
+        Ok((tick.pc, tick.opcode, tick.cost, tick.display_changed))
+    }
+    // runs a 60th of a second, or the budget in microseconds, and returns how many instructions
+    // ran
+    #[pyo3(signature = (budget_us = FRAME_TIME))]
+    fn step_frame(&mut self, budget_us: i64) -> PyResult<u32> {
+        let frame = self.inner.step_frame(budget_us).map_err(error)?;
+        Ok(frame.instructions)
+    }
+
+    fn key_down(&mut self, key: usize) -> PyResult<()> {
+        self.inner.key_down(keypad_key(key)?);
+        Ok(())
+    }
+    fn key_up(&mut self, key: usize) -> PyResult<()> {
+        self.inner.key_up(keypad_key(key)?);
+        Ok(())
+    }
+    fn keypad(&self) -> [bool; 16] {
+        *self.inner.keypad()
+    }
+
+    // (width, height) of the screen
+    fn dimensions(&self) -> (usize, usize) {
+        self.inner.dimensions()
+    }
+    // one bit per pixel, row major
+    fn framebuffer<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new_bound(py, &self.inner.framebuffer())
+    }
+    // one byte per pixel holding the lit planes, row major
+    fn pixels<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new_bound(py, &self.inner.pixels())
+    }
+
+    fn registers(&self) -> Vec<u8> {
+        self.inner.registers().to_vec()
+    }
+    fn index(&self) -> u16 {
+        self.inner.index()
+    }
+    fn pc(&self) -> u16 {
+        self.inner.pc()
+    }
+    fn delay_timer(&self) -> u8 {
+        self.inner.delay_timer()
+    }
+    fn sound_timer(&self) -> u8 {
+        self.inner.sound_timer()
+    }
+    fn memory<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new_bound(py, self.inner.memory())
+    }
+    fn halted(&self) -> bool {
+        self.inner.halted()
+    }
+
+    fn save_state<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let state = self.inner.save_state().map_err(error)?;
+        Ok(PyBytes::new_bound(py, &state))
+    }
+    fn load_state(&mut self, state: &[u8]) -> PyResult<()> {
+        self.inner.load_state(state).map_err(error)
+    }
+}
+
+fn keypad_key(key: usize) -> PyResult<usize> {
+    if key < 16 {
+        Ok(key)
+    } else {
+        Err(PyValueError::new_err(format!(
+            "no key {key}, keys are 0 to 15"
+        )))
+    }
+}
+
+fn error(e: impl std::fmt::Display) -> PyErr {
+    PyRuntimeError::new_err(e.to_string())
+}
+
+#[pymodule]
+fn chip8(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyChip8>()
+}
@@ -0,0 +1,29 @@
+use crate::platform::Platform;
+
+// behaviors that differ between chip-8 interpreters, games expect the ones they were written for.
+// the defaults are the original cosmac vip's, the default platform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quirks {
+    // or/and/xor reset vf to 0
+    pub vf_reset: bool,
+    // fx55/fx65 increment i by x + 1, otherwise i is left unchanged
+    pub memory: bool,
+    // with the memory quirk, i is incremented by x instead of x + 1
+    pub memory_by_x: bool,
+    // 8xy6/8xyE shift vx in place, otherwise vy is shifted into vx
+    pub shifting: bool,
+    // bnnn jumps to xnn + vx, otherwise to nnn + v0
+    pub jumping: bool,
+    // sprites are clipped at the screen edges, otherwise they wrap around
+    pub clipping: bool,
+    // drw waits for the next frame before execution continues
+    pub display_wait: bool,
+    // in low resolution the scroll instructions move half as far, by high resolution pixels
+    pub half_scroll: bool,
+}
+impl Default for Quirks {
+    fn default() -> Self {
+        Platform::default().quirks()
+    }
+}
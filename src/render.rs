@@ -0,0 +1,237 @@
+use crate::{chip8::ColorEffect, frontend::Screen};
+
+// the amber of the original frontend, rgb
+const BACKGROUND_COLOR: [u8; 3] = [153, 102, 1];
+const PIXEL_COLOR: [u8; 3] = [255, 204, 1];
+const PLANE_2_COLOR: [u8; 3] = [255, 102, 1]; // xo-chip's second plane
+const BLEND_COLOR: [u8; 3] = [102, 34, 1]; // xo-chip pixels lit on both planes
+                                           // chip-8x's color board, by the indices in its color map
+const CHIP_8X_COLORS: [[u8; 3]; 8] = [
+    [0, 0, 0],
+    [255, 0, 0],
+    [0, 0, 255],
+    [255, 0, 255],
+    [0, 255, 0],
+    [255, 255, 0],
+    [0, 255, 255],
+    [255, 255, 255],
+];
+const CHIP_8X_BACKGROUNDS: [[u8; 3]; 4] = [[0, 0, 128], [0, 0, 0], [0, 128, 0], [128, 0, 0]];
+
+// turns screens into rgba pixels at the console resolution, with the color effects applied.
+// the pixels are kept between frames so unlit pixels fade out, whatever shows them. only the
+// rows the screen changed in and the ones still fading are redrawn
+#[derive(Debug, Clone, Default)]
+pub struct Renderer {
+    rgba: Vec<u8>,
+    size: (usize, usize),
+    background: [u8; 3],
+    effect: ColorEffect,
+    settled: u64, // bitmask of the rows done fading, which stay as they are until drawn on
+    changed: u64, // bitmask of the rows the last render changed
+}
+impl Renderer {
+    pub fn render(&mut self, screen: &Screen) -> &[u8] {
+        let (width, height) = (screen.width, screen.height);
+        if self.size != (width, height) {
+            self.size = (width, height);
+            self.rgba = vec![0; width * height * 4];
+            self.settled = 0;
+        }
+        let effect = screen.color_effect;
+        let inverted = effect == ColorEffect::InvertOnCollision && screen.collided;
+        let (background, foreground) = match screen.colors {
+            Some(colors) => (CHIP_8X_BACKGROUNDS[colors.background as usize], PIXEL_COLOR),
+            None if inverted => (PIXEL_COLOR, BACKGROUND_COLOR),
+            None => (BACKGROUND_COLOR, PIXEL_COLOR),
+        };
+        // chip-8x colors each 8 pixels of a row on its own, inverting swaps them per pixel
+        let colors_at = |x, y| match screen.colors {
+            Some(colors) => {
+                let foreground = CHIP_8X_COLORS[colors.color_at(x, y) as usize];
+                match inverted {
+                    true => (foreground, background),
+                    false => (background, foreground),
+                }
+            }
+            None => (background, foreground),
+        };
+        // every row fades towards the new colors
+        if (background, effect) != (self.background, self.effect) {
+            self.settled = 0;
+        }
+        self.background = background;
+        self.effect = effect;
+        let fade = match effect {
+            ColorEffect::Ghosting { decay } => decay,
+            _ => 0.3,
+        };
+
+        // neon glows onto the rows around the ones that changed
+        let redraw = match effect {
+            ColorEffect::Neon { bloom_radius } => spread(screen.dirty_rows, bloom_radius),
+            _ => screen.dirty_rows,
+        } | !self.settled;
+
+        self.changed = 0;
+        for (y, row) in self.rgba.chunks_mut(width * 4).enumerate() {
+            if redraw & 1 << y == 0 {
+                continue;
+            }
+            let mut changed = false;
+            for (x, pixel) in row.chunks_mut(4).enumerate() {
+                let before: [u8; 4] = (*pixel).try_into().unwrap();
+                let (background, foreground) = colors_at(x, y);
+                // indexed by the planes lit at a pixel, after the plane priority
+                let palette = [background, foreground, PLANE_2_COLOR, BLEND_COLOR];
+                // fade existing pixels to the background (or glow) to simulate display fading
+                let target = match effect {
+                    ColorEffect::Neon { bloom_radius } => {
+                        let glow = glow(&screen.pixels, width, x, y, bloom_radius);
+                        [0, 1, 2].map(|c| lerp(background[c], foreground[c], glow * 0.5, 0))
+                    }
+                    _ => background,
+                };
+                for c in 0..3 {
+                    pixel[c] = lerp(pixel[c], target[c], fade, 5);
+                }
+                pixel[3] = 0xFF;
+
+                // draw new pixels
+                let lit = screen
+                    .plane_priority
+                    .color_index(screen.pixels[x + y * width]);
+                if lit != 0 {
+                    pixel[..3].copy_from_slice(&palette[lit as usize]);
+                }
+                changed |= *pixel != before;
+            }
+            if !changed {
+                self.settled |= 1 << y;
+            } else {
+                self.settled &= !(1 << y);
+                self.changed |= 1 << y;
+            }
+        }
+        &self.rgba
+    }
+    // the pixels of the last render
+    pub fn rgba(&self) -> &[u8] {
+        &self.rgba
+    }
+    // bitmask of the rows the last render changed, e.g. to only upload those to the gpu
+    pub fn changed_rows(&self) -> u64 {
+        self.changed
+    }
+    // the color around the screen, as of the last render
+    pub fn background(&self) -> [u8; 3] {
+        self.background
+    }
+}
+
+// the rows in a bitmask and the ones within the radius of them
+fn spread(rows: u64, radius: u8) -> u64 {
+    (1..=radius as u32).fold(rows, |spread, distance| {
+        spread | rows.checked_shl(distance).unwrap_or(0) | rows.checked_shr(distance).unwrap_or(0)
+    })
+}
+
+// 1.0 right next to a lit pixel, falling off to 0.0 past the radius
+fn glow(screen: &[u8], width: usize, x: usize, y: usize, radius: u8) -> f32 {
+    let radius = radius as usize;
+    let height = screen.len() / width;
+    let mut nearest = None;
+    for ny in y.saturating_sub(radius)..=(y + radius).min(height - 1) {
+        for nx in x.saturating_sub(radius)..=(x + radius).min(width - 1) {
+            if screen[nx + ny * width] != 0 {
+                let distance = nx.abs_diff(x).max(ny.abs_diff(y));
+                nearest = Some(nearest.map_or(distance, |n: usize| n.min(distance)));
+            }
+        }
+    }
+    nearest.map_or(0.0, |d| 1.0 - d as f32 / (radius + 1) as f32)
+}
+
+fn lerp(start: u8, end: u8, t: f32, min: u8) -> u8 {
+    if start.abs_diff(end) < min {
+        end
+    } else {
+        (start as f32 + (end as f32 - start as f32) * t) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chip8::{Chip8, PlanePriority},
+        platform::Platform,
+    };
+
+    #[test]
+    fn plane_priority_picks_the_color_of_pixels_on_both_planes() {
+        let mut chip8 = Chip8::with_platform(Platform::XoChip);
+        // plane 3, i = 208, drw v0, v0, 1 puts c0 on the first plane and 80 on the second
+        chip8
+            .load(&[0xF3, 0x01, 0xA2, 0x08, 0xD0, 0x01, 0x12, 0x06, 0xC0, 0x80])
+            .unwrap();
+        (0..3).for_each(|_| _ = chip8.tick().unwrap());
+        assert_eq!((chip8.pixel(0, 0), chip8.pixel(1, 0)), (3, 1));
+
+        let render = |plane_priority| {
+            let (width, height) = chip8.dimensions();
+            let mut renderer = Renderer::default();
+            let rgba = renderer.render(&Screen {
+                pixels: chip8.pixels(),
+                width,
+                height,
+                color_effect: ColorEffect::None,
+                plane_priority,
+                collided: false,
+                dirty_rows: u64::MAX,
+                delay_timer: 0,
+                sound_timer: 0,
+                colors: None,
+            });
+            let color = |x: usize| <[u8; 3]>::try_from(&rgba[x * 4..x * 4 + 3]).unwrap();
+            (color(0), color(1))
+        };
+        assert_eq!(render(PlanePriority::Blend), (BLEND_COLOR, PIXEL_COLOR));
+        assert_eq!(render(PlanePriority::Plane1), (PIXEL_COLOR, PIXEL_COLOR));
+        assert_eq!(render(PlanePriority::Plane2), (PLANE_2_COLOR, PIXEL_COLOR));
+    }
+
+    #[test]
+    fn chip_8x_pixels_take_the_color_of_their_zone() {
+        let mut chip8 = Chip8::with_platform(Platform::Chip8X);
+        // v0 = 8, v1 = 0, v2 = aqua, color row 0 from x 8, draw the top of the 0 there
+        chip8
+            .load(&[
+                0x60, 0x08, 0x61, 0x00, 0x62, 0x06, 0xB0, 0x21, 0xA0, 0x00, 0xD0, 0x11, 0x13, 0x0C,
+            ])
+            .unwrap();
+        while chip8.pc() != 0x30C {
+            chip8.tick().unwrap();
+        }
+        let (width, height) = chip8.dimensions();
+        let mut renderer = Renderer::default();
+        let rgba = renderer.render(&Screen {
+            pixels: chip8.pixels(),
+            width,
+            height,
+            color_effect: ColorEffect::None,
+            plane_priority: PlanePriority::Blend,
+            collided: false,
+            dirty_rows: u64::MAX,
+            delay_timer: 0,
+            sound_timer: 0,
+            colors: chip8.colors().copied(),
+        });
+        let color = |x: usize| <[u8; 3]>::try_from(&rgba[x * 4..x * 4 + 3]).unwrap();
+        assert_eq!(
+            (color(8), color(11)),
+            (CHIP_8X_COLORS[6], CHIP_8X_COLORS[6])
+        );
+        assert_eq!(renderer.background(), CHIP_8X_BACKGROUNDS[0]);
+    }
+}
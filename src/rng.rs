@@ -0,0 +1,80 @@
+use rand::{Error, RngCore};
+
+const PAGE_SIZE: usize = 256;
+const INTERPRETER_SIZE: usize = 512; // the vip interpreter fills 0x000 to 0x1ff
+
+// the cosmac vip interpreter's routine for rnd, for games that count on the numbers it draws.
+// pass it to rng on the builder or set_rng. the interpreter keeps a 16 bit counter, and every
+// draw steps it and adds the byte of the interpreter's page 1 its low byte points at to its high
+// byte, which is the number drawn. the vip also steps the counter between draws, here it only
+// steps on draws, so the sequence is the vip's for the same counter but doesn't follow timing
+#[derive(Debug, Clone)]
+pub struct VipRng {
+    page: [u8; PAGE_SIZE], // 0x100 to 0x1ff of the interpreter, its machine code
+    counter: u16,
+}
+impl VipRng {
+    pub fn new(page: [u8; PAGE_SIZE], counter: u16) -> Self {
+        Self { page, counter }
+    }
+    // from a dump of the whole interpreter, none if it isn't 512 bytes
+    pub fn from_interpreter(image: &[u8], counter: u16) -> Option<Self> {
+        if image.len() != INTERPRETER_SIZE {
+            return None;
+        }
+        let page = image[PAGE_SIZE..].try_into().ok()?;
+        Some(Self::new(page, counter))
+    }
+    fn draw(&mut self) -> u8 {
+        let [low, high] = self.counter.wrapping_add(1).to_le_bytes();
+        let high = high.wrapping_add(self.page[low as usize]);
+        self.counter = u16::from_le_bytes([low, high]);
+        high
+    }
+}
+// rnd takes the low byte of next_u32, so the wider numbers are single draws too, to keep one
+// draw per rnd like on the vip. boot garbage is a draw per byte
+impl RngCore for VipRng {
+    fn next_u32(&mut self) -> u32 {
+        self.draw() as u32
+    }
+    fn next_u64(&mut self) -> u64 {
+        self.draw() as u64
+    }
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        dest.fill_with(|| self.draw());
+    }
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chip8::Chip8;
+
+    #[test]
+    fn draws_add_the_interpreter_byte_under_the_counter() {
+        let page = core::array::from_fn(|i| i as u8);
+        let mut rng = VipRng::new(page, 0);
+        let mut draws = [0; 4];
+        rng.fill_bytes(&mut draws);
+        assert_eq!(draws, [1, 3, 6, 10]);
+        // the low byte carries into the high one before it's added to
+        let mut rng = VipRng::new(page, 0x00FF);
+        assert_eq!(rng.draw(), 1);
+
+        assert!(VipRng::from_interpreter(&[0; 256], 0).is_none());
+        let mut image = [0; 512];
+        image[0x101] = 0x42;
+        let rng = VipRng::from_interpreter(&image, 0).unwrap();
+        // rnd v0, ff draws what the routine does
+        let mut chip8 = Chip8::builder().rng(rng).build();
+        chip8.load(&[0xC0, 0xFF, 0xC1, 0xFF]).unwrap();
+        chip8.tick().unwrap();
+        chip8.tick().unwrap();
+        assert_eq!(chip8.registers()[..2], [0x42, 0x42]);
+    }
+}
@@ -0,0 +1,226 @@
+use crate::chip8::Chip8;
+use rhai::{Array, CallFnOptions, Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+// a rhai script automating the machine, e.g. for rom hacks, bots or speedrun tools:
+//
+//     breakpoint(0x2f0);
+//     fn on_breakpoint(pc) { set_reg(3, 99); }
+//     fn on_frame(frame) { if frame == 60 { press(5); } if frame == 65 { release(5); } }
+//
+// the top level runs once when the script is loaded, on_frame before every frame and
+// on_breakpoint before the instruction at each breakpoint runs. functions can't see the top
+// level's variables, state kept between calls goes in the this map. the machine is reached
+// through these functions:
+//
+//     reg(x) set_reg(x, value) index() set_index(addr) pc() set_pc(addr)
+//     peek(addr) poke(addr, value) delay_timer() sound_timer() halted()
+//     press(key) release(key) breakpoint(addr) clear_breakpoint(addr) find(bytes)
+pub struct Script {
+    inner: Arc<Mutex<Inner>>,
+}
+impl Script {
+    // compiles and runs the script's top level, and installs the breakpoint hook if it has an
+    // on_breakpoint function
+    pub fn new(source: &str, chip8: &mut Chip8) -> Result<Self, String> {
+        let shared = Arc::new(Mutex::new(Shared {
+            machine: Chip8::new(),
+            breakpoints: BTreeSet::new(),
+        }));
+        let engine = engine(&shared);
+        let ast = engine.compile(source).map_err(|e| e.to_string())?;
+        let has = |name: &str| {
+            ast.iter_functions()
+                .any(|f| f.name == name && f.params.len() == 1)
+        };
+        let (on_frame, on_breakpoint) = (has("on_frame"), has("on_breakpoint"));
+        let inner = Arc::new(Mutex::new(Inner {
+            engine,
+            ast,
+            scope: Scope::new(),
+            this: Map::new().into(),
+            shared,
+            on_frame,
+        }));
+
+        lock(&inner).with_machine(chip8, |inner| {
+            let Inner {
+                engine, ast, scope, ..
+            } = inner;
+            engine.run_ast_with_scope(scope, ast)
+        })?;
+        if on_breakpoint {
+            let inner = inner.clone();
+            chip8.add_pre_hook(move |chip8, pc, _| {
+                let mut inner = lock(&inner);
+                if !lock(&inner.shared).breakpoints.contains(&pc) {
+                    return;
+                }
+                if let Err(e) = inner.call(chip8, "on_breakpoint", pc as i64) {
+                    eprintln!("error: script: {e}");
+                }
+            });
+        }
+        Ok(Self { inner })
+    }
+
+    // runs on_frame with the number of the frame about to run, e.g. to press keys on cue
+    pub fn frame(&mut self, chip8: &mut Chip8, number: u64) -> Result<(), String> {
+        let mut inner = lock(&self.inner);
+        if !inner.on_frame {
+            return Ok(());
+        }
+        inner.call(chip8, "on_frame", number as i64)
+    }
+}
+
+struct Inner {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    this: Dynamic,
+    shared: Arc<Mutex<Shared>>,
+    on_frame: bool,
+}
+impl Inner {
+    fn call(&mut self, chip8: &mut Chip8, name: &str, arg: i64) -> Result<(), String> {
+        self.with_machine(chip8, |inner| {
+            let Inner {
+                engine,
+                ast,
+                scope,
+                this,
+                ..
+            } = inner;
+            let options = CallFnOptions::new().eval_ast(false).bind_this_ptr(this);
+            // whatever the function returns is ignored
+            engine
+                .call_fn_with_options::<Dynamic>(options, scope, ast, name, (arg,))
+                .map(drop)
+        })
+    }
+
+    // the script's functions reach the machine through the shared slot, so it's swapped in for
+    // the duration of the call
+    fn with_machine(
+        &mut self,
+        chip8: &mut Chip8,
+        run: impl FnOnce(&mut Self) -> Result<(), Box<EvalAltResult>>,
+    ) -> Result<(), String> {
+        std::mem::swap(chip8, &mut lock(&self.shared).machine);
+        let result = run(self);
+        std::mem::swap(chip8, &mut lock(&self.shared).machine);
+        result.map_err(|e| e.to_string())
+    }
+}
+
+// what the script's functions work on
+struct Shared {
+    machine: Chip8,
+    breakpoints: BTreeSet<u16>,
+}
+
+type Fallible<T> = Result<T, Box<EvalAltResult>>;
+
+fn engine(shared: &Arc<Mutex<Shared>>) -> Engine {
+    let mut engine = Engine::new();
+    // stdout may be the terminal frontend's screen
+    engine.on_print(|text| eprintln!("{text}"));
+    engine.on_debug(|text, _, pos| eprintln!("{pos}: {text}"));
+
+    let s = shared.clone();
+    engine.register_fn("reg", move |x: i64| -> Fallible<i64> {
+        Ok(lock(&s).machine.registers()[register(x)?] as i64)
+    });
+    let s = shared.clone();
+    engine.register_fn("set_reg", move |x: i64, value: i64| -> Fallible<()> {
+        lock(&s).machine.set_register(register(x)?, value as u8);
+        Ok(())
+    });
+    let s = shared.clone();
+    engine.register_fn("index", move || lock(&s).machine.index() as i64);
+    let s = shared.clone();
+    engine.register_fn("set_index", move |addr: i64| {
+        lock(&s).machine.set_index(addr as u16)
+    });
+    let s = shared.clone();
+    engine.register_fn("pc", move || lock(&s).machine.pc() as i64);
+    let s = shared.clone();
+    engine.register_fn("set_pc", move |addr: i64| {
+        lock(&s).machine.set_pc(addr as u16)
+    });
+    // addresses wrap around memory like the game's own
+    let s = shared.clone();
+    engine.register_fn("peek", move |addr: i64| {
+        let shared = lock(&s);
+        let memory = shared.machine.memory();
+        memory[addr as usize & (memory.len() - 1)] as i64
+    });
+    let s = shared.clone();
+    engine.register_fn("poke", move |addr: i64, value: i64| {
+        lock(&s).machine.write_memory(addr as u16, &[value as u8])
+    });
+    let s = shared.clone();
+    engine.register_fn("delay_timer", move || lock(&s).machine.delay_timer() as i64);
+    let s = shared.clone();
+    engine.register_fn("sound_timer", move || lock(&s).machine.sound_timer() as i64);
+    let s = shared.clone();
+    engine.register_fn("halted", move || lock(&s).machine.halt().is_some());
+    let s = shared.clone();
+    engine.register_fn("press", move |key: i64| -> Fallible<()> {
+        lock(&s).machine.key_down(key_index(key)?);
+        Ok(())
+    });
+    let s = shared.clone();
+    engine.register_fn("release", move |key: i64| -> Fallible<()> {
+        lock(&s).machine.key_up(key_index(key)?);
+        Ok(())
+    });
+    // the addresses an array of bytes is found at, e.g. find([0x12, 0x34])
+    let s = shared.clone();
+    engine.register_fn("find", move |bytes: Array| -> Fallible<Array> {
+        let bytes = bytes
+            .into_iter()
+            .map(|byte| match byte.as_int() {
+                Ok(byte @ 0..=255) => Ok(byte as u8),
+                _ => Err(format!("find takes bytes, not {byte}").into()),
+            })
+            .collect::<Fallible<Vec<u8>>>()?;
+        let found = lock(&s).machine.find_pattern(&bytes);
+        Ok(found
+            .into_iter()
+            .map(|addr| Dynamic::from(addr as i64))
+            .collect())
+    });
+    let s = shared.clone();
+    engine.register_fn("breakpoint", move |addr: i64| {
+        lock(&s).breakpoints.insert(addr as u16);
+    });
+    let s = shared.clone();
+    engine.register_fn("clear_breakpoint", move |addr: i64| {
+        lock(&s).breakpoints.remove(&(addr as u16));
+    });
+    engine
+}
+
+fn register(x: i64) -> Fallible<usize> {
+    match x {
+        0..=15 => Ok(x as usize),
+        _ => Err(format!("no register v{x}, registers are v0 to v15").into()),
+    }
+}
+
+fn key_index(key: i64) -> Fallible<usize> {
+    match key {
+        0..=15 => Ok(key as usize),
+        _ => Err(format!("no key {key}, keys are 0 to 15").into()),
+    }
+}
+
+// a script that panicked already reported it, the lock is still good to use
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,251 @@
+use crate::{
+    audio::{Beeper, Synth},
+    frontend::{Frontend, Input, Screen, Sound, Title},
+    platform::Platform,
+    render::Renderer,
+};
+use sdl2::{
+    audio::{AudioCallback, AudioDevice, AudioSpecDesired},
+    event::Event,
+    keyboard::Keycode,
+    pixels::{Color, PixelFormatEnum},
+    rect::Rect,
+    render::{Canvas, Texture, TextureCreator},
+    video::{Window, WindowContext},
+    AudioSubsystem, EventPump,
+};
+
+// console constants
+const SCALING_FACTOR: u32 = 16; // console pixel : real pixels
+
+// a window with the screen scaled up, the keyboard's left side as the keypad and a beeper
+pub struct SdlFrontend {
+    canvas: Canvas<Window>,
+    // leaked so the texture can be kept next to it, there's one window for the whole run
+    texture_creator: &'static TextureCreator<WindowContext>,
+    // at the console resolution, only the rows that changed are uploaded every frame
+    texture: Option<(Texture<'static>, (usize, usize))>,
+    renderer: Renderer,
+    event_pump: EventPump,
+    beeper: SdlBeeper,
+    title: Title,
+}
+impl SdlFrontend {
+    // the beep fades in and out over the attack and release times, in milliseconds
+    pub fn new(
+        (width, height): (usize, usize),
+        beep_attack_ms: f32,
+        beep_release_ms: f32,
+    ) -> Result<Self, String> {
+        let ctx = sdl2::init()?;
+        let video = ctx.video()?;
+        let window = video
+            .window(
+                "chip8",
+                width as u32 * SCALING_FACTOR,
+                height as u32 * SCALING_FACTOR,
+            )
+            .opengl()
+            .build()
+            .map_err(|e| e.to_string())?;
+        let canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+        let texture_creator = Box::leak(Box::new(canvas.texture_creator()));
+
+        let beeper = SdlBeeper::new(&ctx.audio()?, beep_attack_ms, beep_release_ms)?;
+
+        Ok(Self {
+            canvas,
+            texture_creator,
+            texture: None,
+            renderer: Renderer::default(),
+            event_pump: ctx.event_pump()?,
+            beeper,
+            title: Title::default(),
+        })
+    }
+}
+impl Frontend for SdlFrontend {
+    fn poll_input(&mut self) -> Vec<Input> {
+        self.event_pump
+            .poll_iter()
+            .filter_map(|event| match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } => Some(Input::Quit),
+                Event::KeyDown {
+                    keycode: Some(Keycode::F1),
+                    ..
+                } => Some(Input::WarmReset),
+                Event::KeyDown {
+                    keycode: Some(Keycode::F2),
+                    ..
+                } => Some(Input::ColdBoot),
+                Event::KeyDown {
+                    keycode: Some(Keycode::F3),
+                    ..
+                } => Some(Input::CycleQuirks),
+                Event::KeyDown {
+                    keycode: Some(Keycode::F5),
+                    ..
+                } => Some(Input::SaveState),
+                Event::KeyDown {
+                    keycode: Some(Keycode::F9),
+                    ..
+                } => Some(Input::LoadState),
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } => button(keycode).map(Input::KeyDown),
+                Event::KeyUp {
+                    keycode: Some(keycode),
+                    ..
+                } => button(keycode).map(Input::KeyUp),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn present(&mut self, screen: &Screen) {
+        let (width, height) = (screen.width, screen.height);
+        self.renderer.render(screen);
+        let (rgba, changed) = (self.renderer.rgba(), self.renderer.changed_rows());
+
+        // the texture matches the console resolution and is stretched over the window, it's
+        // made again when the resolution changes
+        let texture = match &mut self.texture {
+            Some((texture, size)) if *size == (width, height) => {
+                for (y, rows) in runs(changed, height) {
+                    let rect = Rect::new(0, y as i32, width as u32, rows as u32);
+                    let pixels = &rgba[y * width * 4..(y + rows) * width * 4];
+                    texture.update(rect, pixels, width * 4).unwrap();
+                }
+                texture
+            }
+            texture => {
+                let mut created = self
+                    .texture_creator
+                    .create_texture_streaming(PixelFormatEnum::RGBA32, width as u32, height as u32)
+                    .unwrap();
+                created.update(None, rgba, width * 4).unwrap();
+                &mut texture.insert((created, (width, height))).0
+            }
+        };
+
+        // present the texture
+        let [r, g, b] = self.renderer.background();
+        self.canvas.set_draw_color(Color::RGB(r, g, b));
+        self.canvas.clear();
+        self.canvas.copy(texture, None, None).unwrap();
+        self.canvas.present();
+    }
+
+    fn play(&mut self, sound: &Sound) {
+        self.beeper.play(sound);
+    }
+
+    fn show_ended(&mut self, ended: bool) {
+        self.title.ended = ended;
+        self.canvas
+            .window_mut()
+            .set_title(&self.title.to_string())
+            .unwrap();
+    }
+
+    fn show_quirks(&mut self, quirks: Option<Platform>) {
+        self.title.quirks = quirks;
+        self.canvas
+            .window_mut()
+            .set_title(&self.title.to_string())
+            .unwrap();
+    }
+}
+
+// the runs of consecutive rows in a bitmask, as the first row and how many follow
+fn runs(rows: u64, height: usize) -> impl Iterator<Item = (usize, usize)> {
+    let mut y = 0;
+    std::iter::from_fn(move || {
+        while y < height && rows & 1 << y == 0 {
+            y += 1;
+        }
+        let start = y;
+        while y < height && rows & 1 << y != 0 {
+            y += 1;
+        }
+        (start < height).then_some((start, y - start))
+    })
+}
+
+fn button(keycode: Keycode) -> Option<usize> {
+    let index = match keycode {
+        Keycode::Num1 => 0x1,
+        Keycode::Num2 => 0x2,
+        Keycode::Num3 => 0x3,
+        Keycode::Num4 => 0xC,
+        Keycode::Q => 0x4,
+        Keycode::W => 0x5,
+        Keycode::E => 0x6,
+        Keycode::R => 0xD,
+        Keycode::A => 0x7,
+        Keycode::S => 0x8,
+        Keycode::D => 0x9,
+        Keycode::F => 0xE,
+        Keycode::Z => 0xA,
+        Keycode::X => 0x0,
+        Keycode::C => 0xB,
+        Keycode::V => 0xF,
+        // chip-8x's second keypad, the same way round to the right
+        Keycode::Num7 => 0x11,
+        Keycode::Num8 => 0x12,
+        Keycode::Num9 => 0x13,
+        Keycode::Num0 => 0x1C,
+        Keycode::U => 0x14,
+        Keycode::I => 0x15,
+        Keycode::O => 0x16,
+        Keycode::P => 0x1D,
+        Keycode::J => 0x17,
+        Keycode::K => 0x18,
+        Keycode::L => 0x19,
+        Keycode::Semicolon => 0x1E,
+        Keycode::M => 0x1A,
+        Keycode::Comma => 0x10,
+        Keycode::Period => 0x1B,
+        Keycode::Slash => 0x1F,
+        _ => return None,
+    };
+    Some(index)
+}
+
+// sdl's audio output, also usable with other frontends that run sdl
+pub struct SdlBeeper {
+    device: AudioDevice<SynthCallback>,
+}
+impl SdlBeeper {
+    pub fn new(audio: &AudioSubsystem, attack_ms: f32, release_ms: f32) -> Result<Self, String> {
+        let spec = AudioSpecDesired {
+            freq: Some(44100),
+            channels: Some(1),
+            samples: None,
+        };
+        let device = audio.open_playback(None, &spec, |spec| {
+            SynthCallback(Synth::new(spec.freq as f32, attack_ms, release_ms))
+        })?;
+        device.resume();
+        Ok(Self { device })
+    }
+}
+impl Beeper for SdlBeeper {
+    fn play(&mut self, sound: &Sound) {
+        self.device.lock().0.set(sound);
+    }
+}
+
+struct SynthCallback(Synth);
+impl AudioCallback for SynthCallback {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [Self::Channel]) {
+        self.0.fill(out);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{
+    chip8::Chip8,
+    control::{hex_bytes, Command},
+    frontend::Screen,
+    render::Renderer,
+};
+use tiny_http::{Header, Method, Response, Server};
+
+const INDEX: &str = "\
+GET  /screen.png        the screen in the frontend's colors
+GET  /registers         the registers, timers and whether the game is paused or halted, as json
+GET  /memory            all of memory, raw
+GET  /find/HEX          the addresses the bytes are found at, as json, e.g. /find/dead
+POST /pause             stop running the game
+POST /resume            run it again
+POST /savestate/SLOT    save the machine next to the game, like game.SLOT.state
+POST /loadstate/SLOT    load it back
+POST /quit              close the emulator
+";
+
+// a command posted to the server and the client waiting for its answer
+pub struct Request {
+    pub command: Command,
+    request: tiny_http::Request,
+}
+impl Request {
+    // answers "ok", "ok" and what the command found, or the error with a 500
+    pub fn reply(self, result: Result<String, String>) {
+        let response = match result {
+            Ok(found) if found.is_empty() => Response::from_string("ok\n"),
+            Ok(found) => Response::from_string(format!("ok {found}\n")),
+            Err(e) => Response::from_string(format!("error: {e}\n")).with_status_code(500),
+        };
+        // a client that left doesn't need the answer
+        let _ = self.request.respond(response);
+    }
+}
+
+// an http server for looking at a machine running on another computer or in a container, e.g.
+// `curl localhost:8080/registers`. like the control socket it never blocks, the frame loop
+// polls it once per frame and the requests see the machine between frames
+pub struct HttpServer {
+    server: Server,
+    renderer: Renderer,
+}
+impl HttpServer {
+    // listens on an address like 127.0.0.1:8080, or 0.0.0.0:8080 for other machines
+    pub fn bind(addr: &str) -> Result<Self, String> {
+        let server = Server::http(addr).map_err(|e| e.to_string())?;
+        Ok(Self {
+            server,
+            renderer: Renderer::default(),
+        })
+    }
+
+    // answers the requests for the machine's state right away and returns the commands
+    pub fn poll(&mut self, chip8: &Chip8, paused: bool) -> Vec<Request> {
+        let mut requests = Vec::new();
+        while let Ok(Some(request)) = self.server.try_recv() {
+            let path = request.url().split('?').next().unwrap_or_default();
+            let response = match (request.method(), path) {
+                (Method::Get, "/") => Response::from_string(INDEX),
+                (Method::Get, "/screen.png") => match self.screen_png(chip8) {
+                    Ok(png) => Response::from_data(png).with_header(content_type("image/png")),
+                    Err(e) => Response::from_string(format!("error: {e}\n")).with_status_code(500),
+                },
+                (Method::Get, "/registers") => Response::from_string(registers_json(chip8, paused))
+                    .with_header(content_type("application/json")),
+                (Method::Get, "/memory") => Response::from_data(chip8.memory())
+                    .with_header(content_type("application/octet-stream")),
+                (Method::Get, path) if path.starts_with("/find/") => {
+                    match hex_bytes(&path["/find/".len()..]) {
+                        Some(bytes) => Response::from_string(found_json(chip8, &bytes))
+                            .with_header(content_type("application/json")),
+                        None => {
+                            Response::from_string("error: not hex bytes\n").with_status_code(400)
+                        }
+                    }
+                }
+                (Method::Post, path) => match command(path) {
+                    Some(command) => {
+                        requests.push(Request { command, request });
+                        continue;
+                    }
+                    None => Response::from_string("not found\n").with_status_code(404),
+                },
+                _ => Response::from_string("not found\n").with_status_code(404),
+            };
+            let _ = request.respond(response);
+        }
+        requests
+    }
+
+    fn screen_png(&mut self, chip8: &Chip8) -> Result<Vec<u8>, png::EncodingError> {
+        let (width, height) = chip8.dimensions();
+        let rgba = self.renderer.render(&Screen {
+            pixels: chip8.pixels(),
+            width,
+            height,
+            color_effect: chip8.color_effect(),
+            plane_priority: chip8.plane_priority(),
+            collided: false,
+            dirty_rows: u64::MAX,
+            delay_timer: chip8.delay_timer(),
+            sound_timer: chip8.sound_timer(),
+            colors: chip8.colors().copied(),
+        });
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(rgba)?;
+        Ok(png)
+    }
+}
+
+fn command(path: &str) -> Option<Command> {
+    let command = match path.trim_start_matches('/').split_once('/') {
+        None => match path {
+            "/pause" => Command::Pause,
+            "/resume" => Command::Resume,
+            "/quit" => Command::Quit,
+            _ => return None,
+        },
+        Some(("savestate", slot)) if !slot.is_empty() => Command::SaveState(slot.to_string()),
+        Some(("loadstate", slot)) if !slot.is_empty() => Command::LoadState(slot.to_string()),
+        Some(_) => return None,
+    };
+    Some(command)
+}
+
+fn registers_json(chip8: &Chip8, paused: bool) -> String {
+    let v: Vec<String> = chip8.registers().iter().map(u8::to_string).collect();
+    format!(
+        "{{\"v\":[{}],\"i\":{},\"pc\":{},\"sp\":{},\"delay_timer\":{},\"sound_timer\":{},\
+         \"paused\":{},\"halted\":{}}}\n",
+        v.join(","),
+        chip8.index(),
+        chip8.pc(),
+        chip8.sp(),
+        chip8.delay_timer(),
+        chip8.sound_timer(),
+        paused,
+        chip8.halt().is_some(),
+    )
+}
+
+fn found_json(chip8: &Chip8, bytes: &[u8]) -> String {
+    let found: Vec<String> = chip8
+        .find_pattern(bytes)
+        .iter()
+        .map(u16::to_string)
+        .collect();
+    format!("[{}]\n", found.join(","))
+}
+
+fn content_type(value: &str) -> Header {
+    Header::from_bytes("Content-Type", value).unwrap()
+}
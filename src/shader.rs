@@ -0,0 +1,259 @@
+use pixels::{wgpu, Pixels};
+use std::time::Instant;
+
+// what every post-process shader is compiled with. the screen, scaled up by pixels, is sampled
+// through r_tex_color and r_tex_sampler, and r_locals has the window size in pixels and the
+// seconds since the window opened. shaders only write fs_main
+const PRELUDE: &str = "
+struct VertexOutput {
+    @location(0) tex_coord: vec2<f32>,
+    @builtin(position) position: vec4<f32>,
+}
+
+struct Locals {
+    size: vec2<f32>,
+    time: f32,
+    _padding: f32,
+}
+
+@group(0) @binding(0) var r_tex_color: texture_2d<f32>;
+@group(0) @binding(1) var r_tex_sampler: sampler;
+@group(0) @binding(2) var<uniform> r_locals: Locals;
+
+@vertex
+fn vs_main(@location(0) position: vec2<f32>) -> VertexOutput {
+    var out: VertexOutput;
+    out.tex_coord = fma(position, vec2<f32>(0.5, -0.5), vec2<f32>(0.5, 0.5));
+    out.position = vec4<f32>(position, 0.0, 1.0);
+    return out;
+}
+";
+const LOCALS_SIZE: u64 = 16; // bytes
+
+// a full screen pass over the scaled up screen with a wgsl fragment shader, e.g. for crt effects.
+// pixels renders into a texture the size of the window, which the shader draws to the window
+pub struct PostProcess {
+    texture_view: wgpu::TextureView,
+    sampler: wgpu::Sampler,
+    vertex_buffer: wgpu::Buffer,
+    locals: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+    size: (u32, u32),
+    start: Instant,
+}
+impl PostProcess {
+    // compiles the fragment shader, the error says what's wrong with it
+    pub fn new(pixels: &Pixels, source: &str, (width, height): (u32, u32)) -> Result<Self, String> {
+        let device = pixels.device();
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("post_process_shader"),
+            source: wgpu::ShaderSource::Wgsl(format!("{PRELUDE}\n{source}").into()),
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("post_process_sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        // one triangle covering the whole window
+        let vertices: [[f32; 2]; 3] = [[-1.0, -1.0], [3.0, -1.0], [-1.0, 3.0]];
+        let vertex_buffer = wgpu::util::DeviceExt::create_buffer_init(
+            device,
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("post_process_vertex_buffer"),
+                contents: &vertices
+                    .concat()
+                    .iter()
+                    .flat_map(|v| v.to_le_bytes())
+                    .collect::<Vec<_>>(),
+                usage: wgpu::BufferUsages::VERTEX,
+            },
+        );
+        let locals = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("post_process_locals"),
+            size: LOCALS_SIZE,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("post_process_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(LOCALS_SIZE),
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("post_process_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("post_process_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: 8,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x2],
+                }],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: pixels.render_texture_format(),
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+        if let Some(e) = pollster::block_on(device.pop_error_scope()) {
+            return Err(e.to_string());
+        }
+
+        let texture_view = target(pixels, width, height);
+        let bind_group = bind_group(pixels, &bind_group_layout, &texture_view, &sampler, &locals);
+        Ok(Self {
+            texture_view,
+            sampler,
+            vertex_buffer,
+            locals,
+            bind_group_layout,
+            bind_group,
+            pipeline,
+            size: (width, height),
+            start: Instant::now(),
+        })
+    }
+    // the window was resized, in physical pixels
+    pub fn resize(&mut self, pixels: &Pixels, width: u32, height: u32) {
+        self.size = (width, height);
+        self.texture_view = target(pixels, width, height);
+        self.bind_group = bind_group(
+            pixels,
+            &self.bind_group_layout,
+            &self.texture_view,
+            &self.sampler,
+            &self.locals,
+        );
+    }
+    // where pixels should scale the screen to before the pass
+    pub fn texture_view(&self) -> &wgpu::TextureView {
+        &self.texture_view
+    }
+    pub fn render(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        render_target: &wgpu::TextureView,
+    ) {
+        let locals = [
+            self.size.0 as f32,
+            self.size.1 as f32,
+            self.start.elapsed().as_secs_f32(),
+            0.0,
+        ];
+        let locals: Vec<u8> = locals.iter().flat_map(|v| v.to_le_bytes()).collect();
+        queue.write_buffer(&self.locals, 0, &locals);
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("post_process_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: render_target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        pass.draw(0..3, 0..1);
+    }
+}
+
+// the texture pixels scales the screen into, the size of the window
+fn target(pixels: &Pixels, width: u32, height: u32) -> wgpu::TextureView {
+    pixels
+        .device()
+        .create_texture(&wgpu::TextureDescriptor {
+            label: Some("post_process_texture"),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: pixels.render_texture_format(),
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        })
+        .create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+fn bind_group(
+    pixels: &Pixels,
+    layout: &wgpu::BindGroupLayout,
+    texture_view: &wgpu::TextureView,
+    sampler: &wgpu::Sampler,
+    locals: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    pixels
+        .device()
+        .create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("post_process_bind_group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: locals.as_entire_binding(),
+                },
+            ],
+        })
+}
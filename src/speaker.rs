@@ -0,0 +1,66 @@
+use crate::{
+    audio::{Beeper, Synth},
+    frontend::Sound,
+};
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    Device, FromSample, SampleFormat, SizedSample, Stream, StreamConfig,
+};
+use std::sync::{Arc, Mutex};
+
+// the default audio output through cpal, for frontends without an audio library of their own
+pub struct CpalBeeper {
+    _stream: Stream, // plays until it's dropped
+    synth: Arc<Mutex<Synth>>,
+}
+impl CpalBeeper {
+    // the beep fades in and out over the attack and release times, in milliseconds
+    pub fn new(attack_ms: f32, release_ms: f32) -> Result<Self, String> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or("no audio output device")?;
+        let config = device.default_output_config().map_err(|e| e.to_string())?;
+        let synth = Synth::new(config.sample_rate().0 as f32, attack_ms, release_ms);
+        let synth = Arc::new(Mutex::new(synth));
+        let stream = match config.sample_format() {
+            SampleFormat::F32 => stream::<f32>(&device, &config.config(), synth.clone()),
+            SampleFormat::I16 => stream::<i16>(&device, &config.config(), synth.clone()),
+            SampleFormat::U16 => stream::<u16>(&device, &config.config(), synth.clone()),
+            format => return Err(format!("unsupported sample format {format}")),
+        }?;
+        stream.play().map_err(|e| e.to_string())?;
+        Ok(Self {
+            _stream: stream,
+            synth,
+        })
+    }
+}
+impl Beeper for CpalBeeper {
+    fn play(&mut self, sound: &Sound) {
+        self.synth.lock().unwrap().set(sound);
+    }
+}
+
+// the synth's mono samples on every channel of the device
+fn stream<T: SizedSample + FromSample<f32>>(
+    device: &Device,
+    config: &StreamConfig,
+    synth: Arc<Mutex<Synth>>,
+) -> Result<Stream, String> {
+    let channels = config.channels as usize;
+    let mut mono = Vec::new();
+    device
+        .build_output_stream(
+            config,
+            move |out: &mut [T], _| {
+                mono.resize(out.len() / channels, 0.0);
+                synth.lock().unwrap().fill(&mut mono);
+                for (frame, &sample) in out.chunks_mut(channels).zip(&mono) {
+                    frame.fill(T::from_sample(sample));
+                }
+            },
+            |e| eprintln!("error: audio output failed: {e}"),
+            None,
+        )
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,141 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+const STACK_SIZE: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StackError {
+    // more nested calls than the stack depth
+    Overflow(usize),
+    Underflow,
+}
+impl fmt::Display for StackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StackError::Overflow(depth) => {
+                write!(f, "stack overflow, more than {depth} nested calls")
+            }
+            StackError::Underflow => write!(f, "stack underflow, return without a call"),
+        }
+    }
+}
+impl core::error::Error for StackError {}
+
+// return addresses, limited to the depth of the interpreter being emulated. the frames real
+// interpreters have fit in a fixed array, deeper ones go on the heap as they're pushed
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stack {
+    frames: [u16; STACK_SIZE],
+    sp: usize,
+    deeper: Vec<u16>,     // frames past STACK_SIZE, only with a larger depth
+    depth: Option<usize>, // none lets homebrew recurse as deep as it likes
+}
+impl Stack {
+    pub fn new() -> Self {
+        Self::with_depth(Some(STACK_SIZE))
+    }
+    pub fn with_depth(depth: Option<usize>) -> Self {
+        Self {
+            frames: [0; STACK_SIZE],
+            sp: 0,
+            deeper: Vec::new(),
+            depth,
+        }
+    }
+    pub fn push(&mut self, addr: u16) -> Result<(), StackError> {
+        if let Some(depth) = self.depth.filter(|&depth| self.sp >= depth) {
+            return Err(StackError::Overflow(depth));
+        }
+        match self.frames.get_mut(self.sp) {
+            Some(frame) => *frame = addr,
+            None => self.deeper.push(addr),
+        }
+        self.sp += 1;
+        Ok(())
+    }
+    pub fn pop(&mut self) -> Result<u16, StackError> {
+        let addr = self.peek().ok_or(StackError::Underflow)?;
+        if self.sp > STACK_SIZE {
+            self.deeper.pop();
+        }
+        self.sp -= 1;
+        Ok(addr)
+    }
+    // return addresses from the bottom of the stack up
+    pub fn frames(&self) -> impl Iterator<Item = u16> + '_ {
+        self.frames[..self.sp.min(STACK_SIZE)]
+            .iter()
+            .chain(&self.deeper)
+            .copied()
+    }
+    pub fn peek(&self) -> Option<u16> {
+        match self.sp {
+            0 => None,
+            sp if sp > STACK_SIZE => self.deeper.last().copied(),
+            sp => Some(self.frames[sp - 1]),
+        }
+    }
+    // number of frames in use
+    pub fn sp(&self) -> usize {
+        self.sp
+    }
+    pub fn depth(&self) -> Option<usize> {
+        self.depth
+    }
+    // frames already pushed stay, even past a smaller depth
+    pub fn set_depth(&mut self, depth: Option<usize>) {
+        self.depth = depth;
+    }
+}
+impl Default for Stack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pops_in_reverse_push_order() {
+        let mut stack = Stack::new();
+        stack.push(0x202).unwrap();
+        stack.push(0x300).unwrap();
+        assert_eq!((stack.sp(), stack.peek()), (2, Some(0x300)));
+        assert_eq!(stack.pop(), Ok(0x300));
+        assert_eq!(stack.pop(), Ok(0x202));
+        assert_eq!(stack.sp(), 0);
+    }
+
+    #[test]
+    fn pushing_past_the_depth_overflows() {
+        let mut stack = Stack::with_depth(Some(12));
+        (0..12).for_each(|addr| stack.push(addr).unwrap());
+        assert_eq!(stack.push(12), Err(StackError::Overflow(12)));
+        assert_eq!(stack.sp(), 12);
+        assert_eq!(stack.peek(), Some(11));
+    }
+
+    #[test]
+    fn popping_an_empty_stack_underflows() {
+        let mut stack = Stack::new();
+        assert_eq!(stack.pop(), Err(StackError::Underflow));
+        stack.push(0x202).unwrap();
+        stack.pop().unwrap();
+        assert_eq!(stack.pop(), Err(StackError::Underflow));
+    }
+
+    #[test]
+    fn unlimited_depth_goes_past_the_array() {
+        let mut stack = Stack::with_depth(None);
+        (0..40).for_each(|addr| stack.push(addr).unwrap());
+        assert!(stack.frames().eq(0..40));
+        (0..40)
+            .rev()
+            .for_each(|addr| assert_eq!(stack.pop(), Ok(addr)));
+        assert_eq!(stack.pop(), Err(StackError::Underflow));
+    }
+}
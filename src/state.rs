@@ -0,0 +1,632 @@
+use crate::{
+    chip8::{ColorMap, Halt, UnknownOpcode, HIRES_WIDTH, LORES_WIDTH, SCREEN_SIZE},
+    error::Chip8Error,
+    stack::StackError,
+};
+use alloc::{format, vec, vec::Vec};
+use core::fmt;
+
+const MAGIC: &[u8; 4] = b"C8ST";
+const VERSION: u8 = 3; // 1 had a byte for the stack depth, 2 had no chip-8x colors or keypad
+const MEMORY_SIZES: [usize; 3] = [0x1000, 0x2000, 0x10000]; // bytes
+const PLANES: u8 = 0b11; // bitmask of both xo-chip planes
+const NO_KEY: u8 = u8::MAX;
+
+// why a save state can't be loaded, the machine is left as it was
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StateError {
+    // not a save state at all
+    NotAState,
+    // saved by a newer version of the emulator
+    UnsupportedVersion(u8),
+    // cut off before the end
+    Truncated,
+    // a value no machine could be in, e.g. memory that isn't a power of two
+    Invalid,
+    // the machine halted on an error a save state has no room for, like a rom too large to load
+    Unsaveable,
+}
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateError::NotAState => write!(f, "not a save state"),
+            StateError::UnsupportedVersion(version) => {
+                write!(f, "save state version {version} is newer than {VERSION}")
+            }
+            StateError::Truncated => write!(f, "save state is cut off"),
+            StateError::Invalid => write!(f, "save state is corrupt"),
+            StateError::Unsaveable => write!(f, "the machine halted in a way that can't be saved"),
+        }
+    }
+}
+impl core::error::Error for StateError {}
+
+// little endian fields one after another, a save state is only read back by the same layout
+#[derive(Default)]
+struct StateWriter {
+    bytes: Vec<u8>,
+}
+impl StateWriter {
+    pub fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+    pub fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    pub fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    pub fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+    // a length prefixed run of bytes
+    pub fn block(&mut self, bytes: &[u8]) {
+        self.u32(bytes.len() as u32);
+        self.bytes(bytes);
+    }
+    pub fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+struct StateReader<'a> {
+    bytes: &'a [u8],
+}
+impl<'a> StateReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+    pub fn u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.bytes(1)?[0])
+    }
+    pub fn u16(&mut self) -> Result<u16, StateError> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+    pub fn u32(&mut self) -> Result<u32, StateError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+    pub fn bool(&mut self) -> Result<bool, StateError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(StateError::Invalid),
+        }
+    }
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8], StateError> {
+        if self.bytes.len() < len {
+            return Err(StateError::Truncated);
+        }
+        let (bytes, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+    pub fn array<const N: usize>(&mut self) -> Result<[u8; N], StateError> {
+        Ok(self.bytes(N)?.try_into().unwrap())
+    }
+    pub fn block(&mut self) -> Result<&'a [u8], StateError> {
+        let len = self.u32()?;
+        self.bytes(len as usize)
+    }
+    // trailing bytes mean the state wasn't written by this layout
+    pub fn finish(self) -> Result<(), StateError> {
+        match self.bytes {
+            [] => Ok(()),
+            _ => Err(StateError::Invalid),
+        }
+    }
+}
+
+// everything a running game can change, decoded from a save state. the machine copies itself in
+// and out of it, so states can also be compared without a machine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Snapshot {
+    pub mem: Vec<u8>,
+    pub v: [u8; 16],
+    pub ir: u16,
+    pub pc: u16,
+    pub dt: u8,
+    pub st: u8,
+    pub stack: Vec<u16>,
+    pub rpl: [u8; 16],
+    pub hires: bool,
+    pub two_page: bool,
+    pub planes: u8,
+    pub screen: Vec<u8>, // SCREEN_SIZE pixels
+    pub keypad: [bool; 16],
+    pub second_keypad: [bool; 16],
+    pub key_wait: Option<u8>,
+    pub releases: u16,
+    pub presses: Vec<u8>,
+    pub waiting_for_key: bool,
+    pub vblank_wait: bool,
+    pub audio_pattern: Option<[u8; 16]>,
+    pub pitch: u8,
+    pub colors: ColorMap,
+    pub halt: Option<Halt>,
+}
+impl Snapshot {
+    pub fn encode(&self) -> Result<Vec<u8>, StateError> {
+        let mut state = StateWriter::default();
+        state.bytes(MAGIC);
+        state.u8(VERSION);
+        state.block(&self.mem);
+        state.bytes(&self.v);
+        state.u16(self.ir);
+        state.u16(self.pc);
+        state.u8(self.dt);
+        state.u8(self.st);
+        state.u32(self.stack.len() as u32);
+        self.stack.iter().for_each(|&addr| state.u16(addr));
+        state.bytes(&self.rpl);
+        state.bool(self.hires);
+        state.bool(self.two_page);
+        state.u8(self.planes);
+        // four pixels per byte, each is a bitmask of two planes
+        self.screen.chunks(4).for_each(|pixels| {
+            state.u8(pixels
+                .iter()
+                .rev()
+                .fold(0, |byte, &pixel| byte << 2 | pixel));
+        });
+        state.u16(keys(&self.keypad));
+        state.u8(self.key_wait.unwrap_or(NO_KEY));
+        state.u16(self.releases);
+        state.u8(self.presses.len() as u8);
+        self.presses.iter().for_each(|&key| state.u8(key));
+        state.bool(self.waiting_for_key);
+        state.bool(self.vblank_wait);
+        state.bool(self.audio_pattern.is_some());
+        state.bytes(&self.audio_pattern.unwrap_or_default());
+        state.u8(self.pitch);
+        state.u16(keys(&self.second_keypad));
+        state.bytes(&self.colors.foreground);
+        state.u8(self.colors.background);
+        encode_halt(&mut state, self.halt)?;
+        Ok(state.finish())
+    }
+    pub fn decode(bytes: &[u8]) -> Result<Self, StateError> {
+        let mut state = StateReader::new(bytes);
+        if state.bytes(MAGIC.len()) != Ok(MAGIC) {
+            return Err(StateError::NotAState);
+        }
+        let version = match state.u8()? {
+            version @ (1..=VERSION) => version,
+            version => return Err(StateError::UnsupportedVersion(version)),
+        };
+        let mem = state.block()?.to_vec();
+        let v = state.array()?;
+        let (ir, pc, dt, st) = (state.u16()?, state.u16()?, state.u8()?, state.u8()?);
+        let depth = match version {
+            1 => state.u8()? as u32,
+            _ => state.u32()?,
+        };
+        let stack: Vec<u16> = (0..depth).map(|_| state.u16()).collect::<Result<_, _>>()?;
+        let rpl = state.array()?;
+        let (hires, two_page, planes) = (state.bool()?, state.bool()?, state.u8()?);
+        let screen = state
+            .bytes(SCREEN_SIZE / 4)?
+            .iter()
+            .flat_map(|byte| (0..4).map(move |i| byte >> (2 * i) & PLANES))
+            .collect();
+        let keys = state.u16()?;
+        let key_wait = Some(state.u8()?).filter(|&key| key != NO_KEY);
+        let releases = state.u16()?;
+        let presses: Vec<u8> = (0..state.u8()?)
+            .map(|_| state.u8())
+            .collect::<Result<_, _>>()?;
+        let (waiting_for_key, vblank_wait) = (state.bool()?, state.bool()?);
+        let has_pattern = state.bool()?;
+        let pattern = state.array()?;
+        let pitch = state.u8()?;
+        let (second_keys, colors) = match version {
+            1 | 2 => (0, ColorMap::default()),
+            _ => (
+                state.u16()?,
+                ColorMap {
+                    foreground: state.array()?,
+                    background: state.u8()?,
+                },
+            ),
+        };
+        let halt = decode_halt(&mut state, version)?;
+        state.finish()?;
+        // the machine only ever points inside its memory
+        let outside = |addr: u16| addr as usize >= mem.len();
+        if !MEMORY_SIZES.contains(&mem.len())
+            || outside(pc)
+            || outside(ir)
+            || stack.iter().any(|&addr| outside(addr))
+            || planes > PLANES
+            || key_wait.is_some_and(|key| key > 0xF)
+            || presses.iter().any(|&key| key > 0xF)
+            || colors.foreground.iter().any(|&color| color > 7)
+            || colors.background > 3
+        {
+            return Err(StateError::Invalid);
+        }
+        Ok(Self {
+            mem,
+            v,
+            ir,
+            pc,
+            dt,
+            st,
+            stack,
+            rpl,
+            hires,
+            two_page,
+            planes,
+            screen,
+            keypad: held(keys),
+            second_keypad: held(second_keys),
+            key_wait,
+            releases,
+            presses,
+            waiting_for_key,
+            vblank_wait,
+            audio_pattern: has_pattern.then_some(pattern),
+            pitch,
+            colors,
+            halt,
+        })
+    }
+}
+
+// a bit per key, key 0 in the lowest
+fn keys(keypad: &[bool; 16]) -> u16 {
+    keypad
+        .iter()
+        .rev()
+        .fold(0, |mask, &held| mask << 1 | held as u16)
+}
+fn held(keys: u16) -> [bool; 16] {
+    core::array::from_fn(|key| keys & 1 << key != 0)
+}
+
+// halts are a tag byte, crashes follow it with their error
+fn encode_halt(state: &mut StateWriter, halt: Option<Halt>) -> Result<(), StateError> {
+    match halt {
+        None => state.u8(0),
+        Some(Halt::Exit) => state.u8(1),
+        Some(Halt::SysCall) => state.u8(2),
+        Some(Halt::Ended) => state.u8(3),
+        Some(Halt::Crash(e)) => {
+            state.u8(4);
+            match e {
+                Chip8Error::Stack(StackError::Overflow(depth)) => {
+                    state.u8(0);
+                    state.u32(depth as u32);
+                }
+                Chip8Error::Stack(StackError::Underflow) => state.u8(1),
+                Chip8Error::UnknownOpcode(UnknownOpcode { pc, opcode }) => {
+                    state.u8(2);
+                    state.u16(pc);
+                    state.u16(opcode);
+                }
+                Chip8Error::OutOfBounds { pc, addr } => {
+                    state.u8(3);
+                    state.u16(pc);
+                    state.u32(addr as u32);
+                }
+                Chip8Error::ReplayEnded { pc } => {
+                    state.u8(4);
+                    state.u16(pc);
+                }
+                // games crash while running, these only halt a machine deserialized or set up
+                // by hand
+                Chip8Error::RomTooLarge { .. }
+                | Chip8Error::MemoryTooSmall { .. }
+                | Chip8Error::State(_) => return Err(StateError::Unsaveable),
+            }
+        }
+    }
+    Ok(())
+}
+fn decode_halt(state: &mut StateReader, version: u8) -> Result<Option<Halt>, StateError> {
+    let halt = match state.u8()? {
+        0 => return Ok(None),
+        1 => Halt::Exit,
+        2 => Halt::SysCall,
+        3 => Halt::Ended,
+        4 => Halt::Crash(match state.u8()? {
+            0 => StackError::Overflow(match version {
+                1 => state.u16()? as usize,
+                _ => state.u32()? as usize,
+            })
+            .into(),
+            1 => StackError::Underflow.into(),
+            2 => UnknownOpcode {
+                pc: state.u16()?,
+                opcode: state.u16()?,
+            }
+            .into(),
+            3 => Chip8Error::OutOfBounds {
+                pc: state.u16()?,
+                addr: state.u32()? as usize,
+            },
+            4 => Chip8Error::ReplayEnded { pc: state.u16()? },
+            _ => return Err(StateError::Invalid),
+        }),
+        _ => return Err(StateError::Invalid),
+    };
+    Ok(Some(halt))
+}
+
+// a register as reported by diff, the timers and stack pointer included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    V(u8),
+    I,
+    Pc,
+    Delay,
+    Sound,
+    Sp, // frames in use
+}
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Register::V(x) => write!(f, "v{x:x}"),
+            Register::I => write!(f, "i"),
+            Register::Pc => write!(f, "pc"),
+            Register::Delay => write!(f, "dt"),
+            Register::Sound => write!(f, "st"),
+            Register::Sp => write!(f, "sp"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterChange {
+    pub register: Register,
+    pub before: u16,
+    pub after: u16,
+}
+
+// a run of consecutive changed bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryChange {
+    pub addr: usize,
+    pub before: Vec<u8>,
+    pub after: Vec<u8>,
+}
+
+// pixels are bitmasks of the planes lit there, at the resolution of the later state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelChange {
+    pub x: usize,
+    pub y: usize,
+    pub before: u8,
+    pub after: u8,
+}
+
+// what changed between two save states, e.g. over one instruction or while a score went up
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateDiff {
+    pub registers: Vec<RegisterChange>,
+    pub memory: Vec<MemoryChange>,
+    pub pixels: Vec<PixelChange>,
+}
+impl StateDiff {
+    pub fn is_empty(&self) -> bool {
+        self.registers.is_empty() && self.memory.is_empty() && self.pixels.is_empty()
+    }
+}
+// one change per line, e.g. `v3: 0x00 -> 0x1f`
+impl fmt::Display for StateDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hex = |bytes: &[u8]| {
+            let bytes: Vec<_> = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+            bytes.join(" ")
+        };
+        for RegisterChange {
+            register,
+            before,
+            after,
+        } in &self.registers
+        {
+            match register {
+                Register::I | Register::Pc => {
+                    writeln!(f, "{register}: 0x{before:03x} -> 0x{after:03x}")?
+                }
+                _ => writeln!(f, "{register}: 0x{before:02x} -> 0x{after:02x}")?,
+            }
+        }
+        for MemoryChange {
+            addr,
+            before,
+            after,
+        } in &self.memory
+        {
+            writeln!(f, "0x{addr:03x}: {} -> {}", hex(before), hex(after))?;
+        }
+        for PixelChange {
+            x,
+            y,
+            before,
+            after,
+        } in &self.pixels
+        {
+            writeln!(f, "pixel {x},{y}: {before} -> {after}")?;
+        }
+        Ok(())
+    }
+}
+
+// compares two states from save_state. memory past the end of the smaller machine isn't compared
+pub fn diff(before: &[u8], after: &[u8]) -> Result<StateDiff, StateError> {
+    let (before, after) = (Snapshot::decode(before)?, Snapshot::decode(after)?);
+    let registers = [
+        (Register::I, before.ir, after.ir),
+        (Register::Pc, before.pc, after.pc),
+        (Register::Delay, before.dt.into(), after.dt.into()),
+        (Register::Sound, before.st.into(), after.st.into()),
+        (
+            Register::Sp,
+            before.stack.len() as u16,
+            after.stack.len() as u16,
+        ),
+    ];
+    let registers = (0..16)
+        .map(|x| {
+            (
+                Register::V(x),
+                before.v[x as usize].into(),
+                after.v[x as usize].into(),
+            )
+        })
+        .chain(registers)
+        .filter(|(_, before, after)| before != after)
+        .map(|(register, before, after)| RegisterChange {
+            register,
+            before,
+            after,
+        })
+        .collect();
+
+    let mut memory: Vec<MemoryChange> = Vec::new();
+    let bytes = before.mem.iter().zip(&after.mem).enumerate();
+    for (addr, (&old, &new)) in bytes.filter(|(_, (old, new))| old != new) {
+        match memory.last_mut() {
+            Some(run) if run.addr + run.after.len() == addr => {
+                run.before.push(old);
+                run.after.push(new);
+            }
+            _ => memory.push(MemoryChange {
+                addr,
+                before: vec![old],
+                after: vec![new],
+            }),
+        }
+    }
+
+    let width = if after.hires {
+        HIRES_WIDTH
+    } else {
+        LORES_WIDTH
+    };
+    let pixels = before
+        .screen
+        .iter()
+        .zip(&after.screen)
+        .enumerate()
+        .filter(|(_, (old, new))| old != new)
+        .map(|(i, (&before, &after))| PixelChange {
+            x: i % width,
+            y: i / width,
+            before,
+            after,
+        })
+        .collect();
+
+    Ok(StateDiff {
+        registers,
+        memory,
+        pixels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chip8::Chip8;
+
+    #[test]
+    fn states_pointing_outside_memory_dont_load() {
+        let mut chip8 = Chip8::new();
+        // call 0x204, then return from it
+        chip8.load(&[0x22, 0x04, 0x00, 0x00, 0x00, 0xEE]).unwrap();
+        chip8.tick().unwrap();
+        let state = Snapshot::decode(&chip8.save_state().unwrap()).unwrap();
+        assert_eq!(state.stack, [0x202]);
+
+        let forged = |forge: fn(&mut Snapshot)| {
+            let mut forged = Snapshot::decode(&chip8.save_state().unwrap()).unwrap();
+            forge(&mut forged);
+            Snapshot::decode(&forged.encode().unwrap()).err()
+        };
+        assert_eq!(forged(|_| {}), None);
+        assert_eq!(forged(|s| s.pc = 0x1000), Some(StateError::Invalid));
+        assert_eq!(forged(|s| s.ir = 0xFFFF), Some(StateError::Invalid));
+        assert_eq!(forged(|s| s.stack[0] = 0x1202), Some(StateError::Invalid));
+
+        let mut forged = state;
+        forged.pc = 0x1000;
+        let before = chip8.save_state().unwrap();
+        assert!(chip8.load_state(&forged.encode().unwrap()).is_err());
+        assert_eq!(chip8.save_state().unwrap(), before);
+    }
+
+    #[test]
+    fn stacks_deeper_than_a_byte_round_trip() {
+        let mut chip8 = Chip8::builder().stack_depth(None).build();
+        // call 0x200 forever
+        chip8.load(&[0x22, 0x00]).unwrap();
+        (0..300).for_each(|_| _ = chip8.tick().unwrap());
+        let saved = chip8.save_state().unwrap();
+        assert_eq!(Snapshot::decode(&saved).unwrap().stack.len(), 300);
+
+        let mut restored = Chip8::builder().stack_depth(None).build();
+        restored.load_state(&saved).unwrap();
+        assert_eq!(restored.sp(), 300);
+        assert_eq!(restored.save_state().unwrap(), saved);
+    }
+
+    #[test]
+    fn halts_that_dont_come_from_a_game_arent_saved() {
+        let chip8 = Chip8::new();
+        let mut state = Snapshot::decode(&chip8.save_state().unwrap()).unwrap();
+        let too_large = Chip8Error::RomTooLarge {
+            size: 0x1000,
+            max: 0xE00,
+            start: 0x200,
+        };
+        state.halt = Some(Halt::Crash(too_large));
+        assert_eq!(state.encode(), Err(StateError::Unsaveable));
+    }
+
+    #[test]
+    fn diff_lists_what_the_game_changed() {
+        let mut chip8 = Chip8::new();
+        // v3 = 5, i = 300, ld [i], v3, i = 303, drw v0, v0, 1 draws the 05 stored there
+        chip8
+            .load(&[0x63, 0x05, 0xA3, 0x00, 0xF3, 0x55, 0xA3, 0x03, 0xD0, 0x01])
+            .unwrap();
+        let before = chip8.save_state().unwrap();
+        assert!(diff(&before, &before).unwrap().is_empty());
+        (0..5).for_each(|_| _ = chip8.tick().unwrap());
+        let changes = diff(&before, &chip8.save_state().unwrap()).unwrap();
+
+        let registers: Vec<_> = changes
+            .registers
+            .iter()
+            .map(|change| (change.register, change.before, change.after))
+            .collect();
+        assert_eq!(
+            registers,
+            [
+                (Register::V(3), 0, 5),
+                (Register::I, 0, 0x303),
+                (Register::Pc, 0x200, 0x20A)
+            ]
+        );
+        assert_eq!(
+            changes.memory,
+            [MemoryChange {
+                addr: 0x303,
+                before: vec![0],
+                after: vec![5]
+            }]
+        );
+        let pixels: Vec<_> = changes.pixels.iter().map(|p| (p.x, p.y)).collect();
+        assert_eq!(pixels, [(5, 0), (7, 0)]);
+        assert_eq!(
+            changes.to_string(),
+            "v3: 0x00 -> 0x05\ni: 0x000 -> 0x303\npc: 0x200 -> 0x20a\n0x303: 00 -> 05\n\
+             pixel 5,0: 0 -> 1\npixel 7,0: 0 -> 1\n"
+        );
+    }
+}
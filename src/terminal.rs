@@ -0,0 +1,214 @@
+use crate::{
+    audio::Beeper,
+    frontend::{Frontend, Input, Screen, Sound},
+    platform::Platform,
+    render::Renderer,
+};
+use ratatui::{
+    crossterm::{
+        event::{
+            self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+            PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+        },
+        execute, terminal,
+    },
+    layout::{Constraint, Layout},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+    DefaultTerminal,
+};
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+// most terminals only tell when keys are pressed, so keys are held until they haven't repeated
+// for this long
+const KEY_HOLD: Duration = Duration::from_millis(250);
+
+// the screen drawn with half blocks in a terminal, two pixels per character, with the timers
+// in a status bar below it. terminals have no sound, it comes from the beeper if there is one
+pub struct TerminalFrontend {
+    terminal: DefaultTerminal,
+    renderer: Renderer,
+    releases: bool,              // the terminal reports key releases
+    held: [Option<Instant>; 32], // when held keys are let go, if it doesn't
+    ended: bool,
+    quirks: Option<Platform>,
+    beeper: Option<Box<dyn Beeper>>,
+}
+impl TerminalFrontend {
+    pub fn new(beeper: Option<Box<dyn Beeper>>) -> Result<Self, String> {
+        let terminal = ratatui::try_init().map_err(|e| e.to_string())?;
+        // kitty's keyboard protocol has releases, where the terminal speaks it
+        let releases = terminal::supports_keyboard_enhancement().unwrap_or(false)
+            && execute!(
+                io::stdout(),
+                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+            )
+            .is_ok();
+        Ok(Self {
+            terminal,
+            renderer: Renderer::default(),
+            releases,
+            held: [None; 32],
+            ended: false,
+            quirks: None,
+            beeper,
+        })
+    }
+
+    fn key(&mut self, key: KeyEvent) -> Option<Input> {
+        let pressed = key.kind != KeyEventKind::Release;
+        let input = match key.code {
+            // raw mode keeps ctrl-c from reaching the process
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Input::Quit,
+            KeyCode::Esc if pressed => Input::Quit,
+            KeyCode::F(1) if pressed => Input::WarmReset,
+            KeyCode::F(2) if pressed => Input::ColdBoot,
+            KeyCode::F(3) if pressed => Input::CycleQuirks,
+            KeyCode::F(5) if pressed => Input::SaveState,
+            KeyCode::F(9) if pressed => Input::LoadState,
+            KeyCode::Char(c) => {
+                let button = button(c.to_ascii_lowercase())?;
+                if self.releases {
+                    match key.kind {
+                        KeyEventKind::Press => Input::KeyDown(button),
+                        KeyEventKind::Release => Input::KeyUp(button),
+                        KeyEventKind::Repeat => return None,
+                    }
+                } else {
+                    // repeats keep the key held
+                    let was_held = self.held[button].is_some();
+                    self.held[button] = Some(Instant::now() + KEY_HOLD);
+                    if was_held {
+                        return None;
+                    }
+                    Input::KeyDown(button)
+                }
+            }
+            _ => return None,
+        };
+        Some(input)
+    }
+}
+impl Frontend for TerminalFrontend {
+    fn poll_input(&mut self) -> Vec<Input> {
+        let mut inputs = Vec::new();
+        while event::poll(Duration::ZERO).unwrap_or(false) {
+            let Ok(event) = event::read() else { break };
+            if let Event::Key(key) = event {
+                inputs.extend(self.key(key));
+            }
+        }
+        let now = Instant::now();
+        for (button, until) in self.held.iter_mut().enumerate() {
+            if until.is_some_and(|until| until <= now) {
+                *until = None;
+                inputs.push(Input::KeyUp(button));
+            }
+        }
+        inputs
+    }
+
+    fn present(&mut self, screen: &Screen) {
+        let rgba = self.renderer.render(screen);
+        let pixel = |x: usize, y: usize| {
+            let i = (y * screen.width + x) * 4;
+            Color::Rgb(rgba[i], rgba[i + 1], rgba[i + 2])
+        };
+        // the upper half block is the upper pixel, its background the lower one
+        let lines: Vec<Line> = (0..screen.height)
+            .step_by(2)
+            .map(|y| {
+                (0..screen.width)
+                    .map(|x| Span::styled("▀", Style::new().fg(pixel(x, y)).bg(pixel(x, y + 1))))
+                    .collect()
+            })
+            .collect();
+        let mut status = format!("DT {:3}  ST {:3}", screen.delay_timer, screen.sound_timer);
+        if let Some(platform) = self.quirks {
+            status.push_str(&format!("  {platform} quirks"));
+        }
+        if self.ended {
+            status.push_str("  program ended");
+        }
+
+        let drawn = self.terminal.draw(|frame| {
+            let [screen_area, status_area] = Layout::vertical([
+                Constraint::Length(screen.height as u16 / 2),
+                Constraint::Length(1),
+            ])
+            .areas(frame.area());
+            frame.render_widget(Paragraph::new(lines), screen_area);
+            frame.render_widget(Paragraph::new(status), status_area);
+        });
+        if let Err(e) = drawn {
+            eprintln!("error: can't draw the screen: {e}");
+        }
+    }
+
+    fn play(&mut self, sound: &Sound) {
+        if let Some(beeper) = &mut self.beeper {
+            beeper.play(sound);
+        }
+    }
+
+    fn show_ended(&mut self, ended: bool) {
+        self.ended = ended;
+    }
+
+    fn show_quirks(&mut self, quirks: Option<Platform>) {
+        self.quirks = quirks;
+    }
+}
+impl Drop for TerminalFrontend {
+    fn drop(&mut self) {
+        if self.releases {
+            let _ = execute!(io::stdout(), PopKeyboardEnhancementFlags);
+        }
+        ratatui::restore();
+    }
+}
+
+// the same keys as the sdl frontend
+fn button(c: char) -> Option<usize> {
+    let index = match c {
+        '1' => 0x1,
+        '2' => 0x2,
+        '3' => 0x3,
+        '4' => 0xC,
+        'q' => 0x4,
+        'w' => 0x5,
+        'e' => 0x6,
+        'r' => 0xD,
+        'a' => 0x7,
+        's' => 0x8,
+        'd' => 0x9,
+        'f' => 0xE,
+        'z' => 0xA,
+        'x' => 0x0,
+        'c' => 0xB,
+        'v' => 0xF,
+        // chip-8x's second keypad, the same way round to the right
+        '7' => 0x11,
+        '8' => 0x12,
+        '9' => 0x13,
+        '0' => 0x1C,
+        'u' => 0x14,
+        'i' => 0x15,
+        'o' => 0x16,
+        'p' => 0x1D,
+        'j' => 0x17,
+        'k' => 0x18,
+        'l' => 0x19,
+        ';' => 0x1E,
+        'm' => 0x1A,
+        ',' => 0x10,
+        '.' => 0x1B,
+        '/' => 0x1F,
+        _ => return None,
+    };
+    Some(index)
+}
@@ -0,0 +1,54 @@
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "std")]
+const TIMER_RATE: u32 = 60; // hz
+#[cfg(feature = "std")]
+const TIMER_PERIOD: Duration = Duration::new(0, 1_000_000_000 / TIMER_RATE);
+
+// decides how many 60hz timer ticks have passed each time the timers are advanced
+pub trait TimerSource: Send {
+    // frames elapsed since the last call
+    fn elapsed_frames(&mut self) -> u32;
+}
+
+// always reports the same number of frames, for predictable timers in tests
+pub struct MockTimerSource(pub u32);
+impl TimerSource for MockTimerSource {
+    fn elapsed_frames(&mut self) -> u32 {
+        self.0
+    }
+}
+
+// counts real 60hz periods, carrying partial periods over to the next call
+#[cfg(feature = "std")]
+pub struct WallClockTimerSource {
+    last: Instant,
+    remainder: Duration,
+}
+#[cfg(feature = "std")]
+impl WallClockTimerSource {
+    pub fn new() -> Self {
+        Self {
+            last: Instant::now(),
+            remainder: Duration::ZERO,
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl Default for WallClockTimerSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+#[cfg(feature = "std")]
+impl TimerSource for WallClockTimerSource {
+    fn elapsed_frames(&mut self) -> u32 {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last) + self.remainder;
+        let frames = (elapsed.as_nanos() / TIMER_PERIOD.as_nanos()) as u32;
+        self.remainder = elapsed - TIMER_PERIOD * frames;
+        self.last = now;
+        frames
+    }
+}
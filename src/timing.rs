@@ -0,0 +1,184 @@
+use crate::disasm::InstructionClass;
+use core::str::FromStr;
+
+const FRAME_TIME: i64 = 1_000_000 / 60; // microseconds per 60hz frame
+
+// microseconds each instruction class takes, indexed by class. mnemonics only come in when a
+// class is tuned by name, and serialized tables map each mnemonic to its cost
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "BTreeMap<String, i64>", try_from = "BTreeMap<String, i64>")
+)]
+pub struct TimingTable {
+    costs: [i64; InstructionClass::COUNT],
+}
+impl TimingTable {
+    // the cosmac vip interpreter, which takes very different times per instruction
+    pub fn vip() -> Self {
+        let costs = [
+            ("cls", 109),
+            ("ret", 105),
+            ("scd n", 109),
+            ("scu n", 109),
+            ("scr", 109),
+            ("scl", 109),
+            ("exit", 109),
+            ("low", 109),
+            ("high", 109),
+            ("jp nnn", 105),
+            ("call nnn", 105),
+            ("se vx, nn", 55),
+            ("sne vx, nn", 55),
+            ("se vx, vy", 73),
+            ("nadd vx, vy", 200),
+            ("save vx - vy", 605),
+            ("load vx - vy", 605),
+            ("ld vx, nn", 27),
+            ("add vx, nn", 45),
+            ("ld vx, vy", 200),
+            ("or vx, vy", 200),
+            ("and vx, vy", 200),
+            ("xor vx, vy", 200),
+            ("add vx, vy", 200),
+            ("sub vx, vy", 200),
+            ("shr vx, vy", 200),
+            ("subn vx, vy", 200),
+            ("shl vx, vy", 200),
+            ("sne vx, vy", 73),
+            ("ld i, nnn", 55),
+            ("jp v0, nnn", 105),
+            ("rnd vx, nn", 164),
+            ("drw vx, vy, n", 22734),
+            ("skp vx", 73),
+            ("sknp vx", 73),
+            ("skp2 vx", 73),
+            ("sknp2 vx", 73),
+            ("ld i, long", 55),
+            ("audio", 605),
+            ("plane n", 45),
+            ("ld vx, dt", 45),
+            ("ld dt, vx", 45),
+            ("ld st, vx", 45),
+            ("add i, vx", 86),
+            ("ld f, vx", 91),
+            ("ld hf, vx", 91),
+            ("pitch vx", 45),
+            ("ld b, vx", 927),
+            ("ld [i], vx", 605),
+            ("ld vx, [i]", 605),
+            ("ld r, vx", 605),
+            ("ld vx, r", 605),
+        ];
+        // the rest, like sys calls and data, take a typical time
+        let mut table = Self {
+            costs: [100; InstructionClass::COUNT],
+        };
+        for (mnemonic, cost) in costs {
+            table
+                .set(mnemonic, cost)
+                .expect("the vip table only has known classes");
+        }
+        table
+    }
+    // the hp48 interpreters run about 30 instructions per frame, whatever they are
+    pub fn super_chip() -> Self {
+        Self::per_frame(30)
+    }
+    // every instruction takes the same time, so this many run per frame
+    pub fn per_frame(instructions: u32) -> Self {
+        Self {
+            costs: [FRAME_TIME / instructions.max(1) as i64; InstructionClass::COUNT],
+        }
+    }
+    pub fn cost(&self, op: u16) -> i64 {
+        self.class_cost(InstructionClass::of(op))
+    }
+    pub fn class_cost(&self, class: InstructionClass) -> i64 {
+        self.costs[class as usize]
+    }
+    // tunes a single instruction class by its mnemonic, e.g. "drw vx, vy, n" to slow down
+    // drawing. none if no class has the mnemonic
+    pub fn set(&mut self, mnemonic: &str, cost: i64) -> Option<()> {
+        let class = InstructionClass::from_mnemonic(mnemonic)?;
+        self.costs[class as usize] = cost;
+        Some(())
+    }
+}
+impl Default for TimingTable {
+    fn default() -> Self {
+        Self::vip()
+    }
+}
+impl FromStr for TimingTable {
+    type Err = ();
+
+    // vip, schip, fast or a number of instructions per frame
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "vip" => Ok(Self::vip()),
+            "schip" => Ok(Self::super_chip()),
+            "fast" => Ok(Self::per_frame(1000)),
+            n => n.parse().map(Self::per_frame).map_err(|_| ()),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+use alloc::collections::BTreeMap;
+
+// sorted by mnemonic, so serialized tables always come out the same
+#[cfg(feature = "serde")]
+impl From<TimingTable> for BTreeMap<String, i64> {
+    fn from(table: TimingTable) -> Self {
+        InstructionClass::ALL
+            .iter()
+            .map(|&class| (class.mnemonic().to_string(), table.class_cost(class)))
+            .collect()
+    }
+}
+#[cfg(feature = "serde")]
+impl TryFrom<BTreeMap<String, i64>> for TimingTable {
+    type Error = String;
+
+    // every class needs a cost, so a table from another version doesn't load half set up
+    fn try_from(costs: BTreeMap<String, i64>) -> Result<Self, Self::Error> {
+        let mut table = Self::per_frame(1);
+        for (mnemonic, &cost) in &costs {
+            table
+                .set(mnemonic, cost)
+                .ok_or_else(|| format!("no instruction class is called \"{mnemonic}\""))?;
+        }
+        match InstructionClass::ALL
+            .iter()
+            .find(|class| !costs.contains_key(class.mnemonic()))
+        {
+            Some(class) => Err(format!("no cost for \"{}\"", class.mnemonic())),
+            None => Ok(table),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classes_are_tuned_by_their_mnemonics() {
+        for class in InstructionClass::ALL {
+            assert_eq!(
+                InstructionClass::from_mnemonic(class.mnemonic()),
+                Some(class)
+            );
+        }
+        let mut timing = TimingTable::vip();
+        assert_eq!(timing.cost(0xD015), 22734);
+        assert_eq!(timing.set("drw vx, vy, n", 100), Some(()));
+        assert_eq!(timing.cost(0xD015), 100);
+        assert_eq!(timing.set("draw", 100), None);
+        // data words and sys calls take the typical time
+        assert_eq!(timing.cost(0x5124), 100);
+        assert_eq!(timing.cost(0x0123), 100);
+    }
+}
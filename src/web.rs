@@ -0,0 +1,245 @@
+use crate::{
+    audio::{Beeper, Synth},
+    chip8::Chip8,
+    frontend::{Frontend, Input, RunOptions, Runner, Screen, Sound, Title},
+    platform::Platform,
+    render::Renderer,
+};
+use std::{cell::RefCell, rc::Rc};
+use wasm_bindgen::{prelude::*, Clamped, JsCast};
+use web_sys::{
+    AudioContext, AudioProcessingEvent, CanvasRenderingContext2d, HtmlCanvasElement, ImageData,
+    ScriptProcessorNode,
+};
+
+const BEEP_ATTACK_MS: f32 = 2.0;
+const BEEP_RELEASE_MS: f32 = 2.0;
+const AUDIO_BUFFER_SIZE: u32 = 1024; // samples
+
+// the emulator in a browser page, drawn on a canvas and heard through webaudio. the page calls
+// frame from requestAnimationFrame and passes keyboard events on, see web/index.html
+#[wasm_bindgen]
+pub struct WebEmulator {
+    chip8: Chip8,
+    runner: Runner,
+    frontend: WebFrontend,
+}
+#[wasm_bindgen]
+impl WebEmulator {
+    // the platform is detected for known games when it's left out
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        canvas: HtmlCanvasElement,
+        game: &[u8],
+        platform: Option<String>,
+    ) -> Result<WebEmulator, JsValue> {
+        let mut builder = Chip8::builder();
+        if let Some(name) = platform {
+            let platform = name
+                .parse::<Platform>()
+                .map_err(|_| JsValue::from_str(&format!("unknown platform {name}")))?;
+            builder = builder.platform(platform);
+        }
+        let mut chip8 = builder.build();
+        chip8.load(game).map_err(error)?;
+        chip8.cold_boot();
+        Ok(Self {
+            chip8,
+            runner: Runner::new(RunOptions::default()),
+            frontend: WebFrontend::new(canvas)?,
+        })
+    }
+
+    // runs and draws the next 60th of a second, false once the game exited
+    pub fn frame(&mut self) -> Result<bool, JsValue> {
+        self.runner
+            .frame(&mut self.chip8, &mut self.frontend)
+            .map_err(error)
+    }
+
+    // takes a KeyboardEvent.code, true if it's one of the emulator's keys
+    pub fn key_down(&mut self, code: &str) -> bool {
+        // browsers only let sound start after the player did something
+        self.frontend.beeper.resume();
+        let input = match code {
+            "F1" => Input::WarmReset,
+            "F2" => Input::ColdBoot,
+            "F3" => Input::CycleQuirks,
+            code => match button(code) {
+                Some(key) => Input::KeyDown(key),
+                None => return false,
+            },
+        };
+        self.frontend.inputs.push(input);
+        true
+    }
+    pub fn key_up(&mut self, code: &str) -> bool {
+        let Some(key) = button(code) else {
+            return false;
+        };
+        self.frontend.inputs.push(Input::KeyUp(key));
+        true
+    }
+
+    // the game ended and its screen is final, until it's reset
+    pub fn ended(&self) -> bool {
+        self.frontend.title.ended
+    }
+    // for the page title, naming the quirks the game runs with, which F3 cycles through
+    pub fn title(&self) -> String {
+        self.frontend.title.to_string()
+    }
+}
+
+struct WebFrontend {
+    canvas: HtmlCanvasElement,
+    context: CanvasRenderingContext2d,
+    renderer: Renderer,
+    inputs: Vec<Input>,
+    title: Title,
+    beeper: WebBeeper,
+}
+impl WebFrontend {
+    fn new(canvas: HtmlCanvasElement) -> Result<Self, JsValue> {
+        let context = canvas
+            .get_context("2d")?
+            .ok_or_else(|| JsValue::from_str("the canvas has no 2d context"))?
+            .dyn_into::<CanvasRenderingContext2d>()?;
+        Ok(Self {
+            canvas,
+            context,
+            renderer: Renderer::default(),
+            inputs: Vec::new(),
+            title: Title::default(),
+            beeper: WebBeeper::new(BEEP_ATTACK_MS, BEEP_RELEASE_MS)?,
+        })
+    }
+}
+impl Frontend for WebFrontend {
+    fn poll_input(&mut self) -> Vec<Input> {
+        std::mem::take(&mut self.inputs)
+    }
+
+    // the canvas matches the console resolution, the page scales it up
+    fn present(&mut self, screen: &Screen) {
+        let (width, height) = (screen.width as u32, screen.height as u32);
+        if (self.canvas.width(), self.canvas.height()) != (width, height) {
+            self.canvas.set_width(width);
+            self.canvas.set_height(height);
+        }
+        let rgba = self.renderer.render(screen);
+        let image = ImageData::new_with_u8_clamped_array_and_sh(Clamped(rgba), width, height);
+        if let Ok(image) = image {
+            let _ = self.context.put_image_data(&image, 0.0, 0.0);
+        }
+    }
+
+    fn play(&mut self, sound: &Sound) {
+        self.beeper.play(sound);
+    }
+
+    fn show_ended(&mut self, ended: bool) {
+        self.title.ended = ended;
+    }
+
+    fn show_quirks(&mut self, quirks: Option<Platform>) {
+        self.title.quirks = quirks;
+    }
+}
+
+// the shared synth, pulled by a script processor node
+pub struct WebBeeper {
+    context: AudioContext,
+    synth: Rc<RefCell<Synth>>,
+    _node: ScriptProcessorNode,
+    _callback: Closure<dyn FnMut(AudioProcessingEvent)>,
+}
+impl WebBeeper {
+    pub fn new(attack_ms: f32, release_ms: f32) -> Result<Self, JsValue> {
+        let context = AudioContext::new()?;
+        let synth = Rc::new(RefCell::new(Synth::new(
+            context.sample_rate(),
+            attack_ms,
+            release_ms,
+        )));
+        let node = context
+            .create_script_processor_with_buffer_size_and_number_of_input_channels_and_number_of_output_channels(
+                AUDIO_BUFFER_SIZE,
+                0,
+                1,
+            )?;
+        let callback = {
+            let synth = synth.clone();
+            let mut samples = Vec::new();
+            Closure::<dyn FnMut(AudioProcessingEvent)>::new(move |event: AudioProcessingEvent| {
+                let Ok(out) = event.output_buffer() else {
+                    return;
+                };
+                samples.resize(out.length() as usize, 0.0);
+                synth.borrow_mut().fill(&mut samples);
+                let _ = out.copy_to_channel(&samples, 0);
+            })
+        };
+        node.set_onaudioprocess(Some(callback.as_ref().unchecked_ref()));
+        node.connect_with_audio_node(&context.destination())?;
+        Ok(Self {
+            context,
+            synth,
+            _node: node,
+            _callback: callback,
+        })
+    }
+    pub fn resume(&self) {
+        let _ = self.context.resume();
+    }
+}
+impl Beeper for WebBeeper {
+    fn play(&mut self, sound: &Sound) {
+        self.synth.borrow_mut().set(sound);
+    }
+}
+
+fn error(e: impl std::fmt::Display) -> JsValue {
+    JsValue::from_str(&e.to_string())
+}
+
+// the same keys as the sdl frontend, by position so other layouts work too
+fn button(code: &str) -> Option<usize> {
+    let index = match code {
+        "Digit1" => 0x1,
+        "Digit2" => 0x2,
+        "Digit3" => 0x3,
+        "Digit4" => 0xC,
+        "KeyQ" => 0x4,
+        "KeyW" => 0x5,
+        "KeyE" => 0x6,
+        "KeyR" => 0xD,
+        "KeyA" => 0x7,
+        "KeyS" => 0x8,
+        "KeyD" => 0x9,
+        "KeyF" => 0xE,
+        "KeyZ" => 0xA,
+        "KeyX" => 0x0,
+        "KeyC" => 0xB,
+        "KeyV" => 0xF,
+        // chip-8x's second keypad, the same way round to the right
+        "Digit7" => 0x11,
+        "Digit8" => 0x12,
+        "Digit9" => 0x13,
+        "Digit0" => 0x1C,
+        "KeyU" => 0x14,
+        "KeyI" => 0x15,
+        "KeyO" => 0x16,
+        "KeyP" => 0x1D,
+        "KeyJ" => 0x17,
+        "KeyK" => 0x18,
+        "KeyL" => 0x19,
+        "Semicolon" => 0x1E,
+        "KeyM" => 0x1A,
+        "Comma" => 0x10,
+        "Period" => 0x1B,
+        "Slash" => 0x1F,
+        _ => return None,
+    };
+    Some(index)
+}
@@ -0,0 +1,214 @@
+use crate::{
+    audio::Beeper,
+    frontend::{Frontend, Input, Screen, Sound, Title},
+    platform::Platform,
+    render::Renderer,
+    shader::PostProcess,
+};
+use pixels::{wgpu, Pixels, SurfaceTexture};
+use winit::{
+    dpi::LogicalSize,
+    event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    platform::run_return::EventLoopExtRunReturn,
+    window::{Window, WindowBuilder},
+};
+
+const SCALING_FACTOR: u32 = 16; // console pixel : real pixels
+
+// a window like the sdl one, drawn with pixels and winit so it builds without the sdl2 headers.
+// winit has no sound, it comes from the beeper if there is one
+pub struct WindowFrontend {
+    event_loop: EventLoop<()>,
+    window: Window,
+    pixels: Pixels,
+    size: (usize, usize),
+    renderer: Renderer,
+    beeper: Option<Box<dyn Beeper>>,
+    post_process: Option<PostProcess>,
+    title: Title,
+}
+impl WindowFrontend {
+    pub fn new(
+        (width, height): (usize, usize),
+        beeper: Option<Box<dyn Beeper>>,
+    ) -> Result<Self, String> {
+        let event_loop = EventLoop::new();
+        let window = WindowBuilder::new()
+            .with_title("chip8")
+            .with_inner_size(LogicalSize::new(
+                width as u32 * SCALING_FACTOR,
+                height as u32 * SCALING_FACTOR,
+            ))
+            .build(&event_loop)
+            .map_err(|e| e.to_string())?;
+        let surface_size = window.inner_size();
+        let surface = SurfaceTexture::new(surface_size.width, surface_size.height, &window);
+        let pixels =
+            Pixels::new(width as u32, height as u32, surface).map_err(|e| e.to_string())?;
+        Ok(Self {
+            event_loop,
+            window,
+            pixels,
+            size: (width, height),
+            renderer: Renderer::default(),
+            beeper,
+            post_process: None,
+            title: Title::default(),
+        })
+    }
+    // runs the screen through a wgsl fragment shader on the gpu, see shader.rs for what it gets
+    pub fn with_shader(mut self, source: &str) -> Result<Self, String> {
+        let size = self.window.inner_size();
+        self.post_process = Some(PostProcess::new(
+            &self.pixels,
+            source,
+            (size.width, size.height),
+        )?);
+        Ok(self)
+    }
+}
+impl Frontend for WindowFrontend {
+    fn poll_input(&mut self) -> Vec<Input> {
+        let mut inputs = Vec::new();
+        let pixels = &mut self.pixels;
+        let post_process = &mut self.post_process;
+        // handles what's queued up and returns, instead of handing the loop over to winit
+        self.event_loop.run_return(|event, _, control_flow| {
+            *control_flow = ControlFlow::Poll;
+            let event = match event {
+                Event::WindowEvent { event, .. } => event,
+                Event::MainEventsCleared => {
+                    *control_flow = ControlFlow::Exit;
+                    return;
+                }
+                _ => return,
+            };
+            match event {
+                WindowEvent::CloseRequested => inputs.push(Input::Quit),
+                WindowEvent::Resized(size) => {
+                    let _ = pixels.resize_surface(size.width, size.height);
+                    if let Some(post_process) = post_process {
+                        post_process.resize(pixels, size.width, size.height);
+                    }
+                }
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            virtual_keycode: Some(keycode),
+                            state,
+                            ..
+                        },
+                    ..
+                } => inputs.extend(input(keycode, state)),
+                _ => {}
+            }
+        });
+        inputs
+    }
+
+    fn present(&mut self, screen: &Screen) {
+        if self.size != (screen.width, screen.height) {
+            self.size = (screen.width, screen.height);
+            let _ = self
+                .pixels
+                .resize_buffer(screen.width as u32, screen.height as u32);
+        }
+        let rgba = self.renderer.render(screen);
+        self.pixels.frame_mut().copy_from_slice(rgba);
+        let [r, g, b] = self.renderer.background().map(|c| c as f64 / 255.0);
+        self.pixels.clear_color(wgpu::Color { r, g, b, a: 1.0 });
+        let drawn = match &self.post_process {
+            // scaled up into the shader's input, which the shader draws to the window
+            Some(post_process) => self.pixels.render_with(|encoder, render_target, context| {
+                context
+                    .scaling_renderer
+                    .render(encoder, post_process.texture_view());
+                post_process.render(&context.queue, encoder, render_target);
+                Ok(())
+            }),
+            None => self.pixels.render(),
+        };
+        if let Err(e) = drawn {
+            eprintln!("error: can't draw the screen: {e}");
+        }
+    }
+
+    fn play(&mut self, sound: &Sound) {
+        if let Some(beeper) = &mut self.beeper {
+            beeper.play(sound);
+        }
+    }
+
+    fn show_ended(&mut self, ended: bool) {
+        self.title.ended = ended;
+        self.window.set_title(&self.title.to_string());
+    }
+
+    fn show_quirks(&mut self, quirks: Option<Platform>) {
+        self.title.quirks = quirks;
+        self.window.set_title(&self.title.to_string());
+    }
+}
+
+// the same keys as the sdl frontend
+fn input(keycode: VirtualKeyCode, state: ElementState) -> Option<Input> {
+    let pressed = state == ElementState::Pressed;
+    let input = match keycode {
+        VirtualKeyCode::Escape if pressed => Input::Quit,
+        VirtualKeyCode::F1 if pressed => Input::WarmReset,
+        VirtualKeyCode::F2 if pressed => Input::ColdBoot,
+        VirtualKeyCode::F3 if pressed => Input::CycleQuirks,
+        VirtualKeyCode::F5 if pressed => Input::SaveState,
+        VirtualKeyCode::F9 if pressed => Input::LoadState,
+        keycode => {
+            let key = button(keycode)?;
+            if pressed {
+                Input::KeyDown(key)
+            } else {
+                Input::KeyUp(key)
+            }
+        }
+    };
+    Some(input)
+}
+
+fn button(keycode: VirtualKeyCode) -> Option<usize> {
+    let index = match keycode {
+        VirtualKeyCode::Key1 => 0x1,
+        VirtualKeyCode::Key2 => 0x2,
+        VirtualKeyCode::Key3 => 0x3,
+        VirtualKeyCode::Key4 => 0xC,
+        VirtualKeyCode::Q => 0x4,
+        VirtualKeyCode::W => 0x5,
+        VirtualKeyCode::E => 0x6,
+        VirtualKeyCode::R => 0xD,
+        VirtualKeyCode::A => 0x7,
+        VirtualKeyCode::S => 0x8,
+        VirtualKeyCode::D => 0x9,
+        VirtualKeyCode::F => 0xE,
+        VirtualKeyCode::Z => 0xA,
+        VirtualKeyCode::X => 0x0,
+        VirtualKeyCode::C => 0xB,
+        VirtualKeyCode::V => 0xF,
+        // chip-8x's second keypad, the same way round to the right
+        VirtualKeyCode::Key7 => 0x11,
+        VirtualKeyCode::Key8 => 0x12,
+        VirtualKeyCode::Key9 => 0x13,
+        VirtualKeyCode::Key0 => 0x1C,
+        VirtualKeyCode::U => 0x14,
+        VirtualKeyCode::I => 0x15,
+        VirtualKeyCode::O => 0x16,
+        VirtualKeyCode::P => 0x1D,
+        VirtualKeyCode::J => 0x17,
+        VirtualKeyCode::K => 0x18,
+        VirtualKeyCode::L => 0x19,
+        VirtualKeyCode::Semicolon => 0x1E,
+        VirtualKeyCode::M => 0x1A,
+        VirtualKeyCode::Comma => 0x10,
+        VirtualKeyCode::Period => 0x1B,
+        VirtualKeyCode::Slash => 0x1F,
+        _ => return None,
+    };
+    Some(index)
+}
@@ -0,0 +1,146 @@
+use crate::chip8::Chip8;
+use std::{
+    sync::mpsc::{self, Receiver, RecvError, Sender, TryRecvError},
+    thread::{self, JoinHandle},
+};
+
+const FRAME_BUDGET: i64 = 1_000_000 / 60; // microseconds per 60hz frame
+
+pub enum Command {
+    // run one frame worth of instructions, advance the timers and publish the frame
+    Tick,
+    SetKey(usize, bool),
+    Reset,
+    // publish the current frame without running anything
+    Snapshot,
+}
+
+pub struct Frame {
+    pub screen: Vec<u8>,
+    pub width: usize,
+    pub height: usize,
+    pub tone: bool,
+    pub halted: bool,
+}
+impl Frame {
+    fn capture(chip8: &Chip8) -> Self {
+        let (width, height) = chip8.dimensions();
+        Self {
+            screen: chip8.pixels(),
+            width,
+            height,
+            tone: chip8.tone(),
+            halted: chip8.halted(),
+        }
+    }
+}
+
+// runs a chip8 on a worker thread, so a gui framework can keep the main thread to itself.
+// the emulator only advances when told to, which keeps runs deterministic.
+pub struct EmulatorHandle {
+    commands: Option<Sender<Command>>,
+    frames: Receiver<Frame>,
+    thread: Option<JoinHandle<()>>,
+}
+impl EmulatorHandle {
+    pub fn spawn(mut chip8: Chip8) -> Self {
+        let (commands, command_rx) = mpsc::channel();
+        let (frame_tx, frames) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            // stops once the handle hangs up
+            for command in command_rx {
+                match command {
+                    Command::Tick => {
+                        // a crash halts the machine, which the frame reports
+                        let _ = chip8.step_frame(FRAME_BUDGET);
+                    }
+                    Command::SetKey(key, true) => {
+                        chip8.key_down(key);
+                        continue;
+                    }
+                    Command::SetKey(key, false) => {
+                        chip8.key_up(key);
+                        continue;
+                    }
+                    Command::Reset => {
+                        chip8.cold_boot();
+                        continue;
+                    }
+                    Command::Snapshot => {}
+                }
+                if frame_tx.send(Frame::capture(&chip8)).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            commands: Some(commands),
+            frames,
+            thread: Some(thread),
+        }
+    }
+
+    pub fn send(&self, command: Command) {
+        // a closed channel means the worker died, which the next frame read reports
+        if let Some(commands) = &self.commands {
+            let _ = commands.send(command);
+        }
+    }
+    pub fn tick(&self) {
+        self.send(Command::Tick);
+    }
+    pub fn set_key(&self, key: usize, pressed: bool) {
+        self.send(Command::SetKey(key, pressed));
+    }
+    pub fn reset(&self) {
+        self.send(Command::Reset);
+    }
+    pub fn snapshot(&self) {
+        self.send(Command::Snapshot);
+    }
+
+    // blocks until the worker publishes its next frame
+    pub fn recv_frame(&self) -> Result<Frame, RecvError> {
+        self.frames.recv()
+    }
+    pub fn try_recv_frame(&self) -> Result<Frame, TryRecvError> {
+        self.frames.try_recv()
+    }
+}
+impl Drop for EmulatorHandle {
+    fn drop(&mut self) {
+        // hang up so the worker's command loop ends, then wait for it
+        self.commands.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticking_publishes_the_frame() {
+        let mut chip8 = Chip8::new();
+        // draws the 0 digit in the top left corner, then loops forever
+        chip8.load(&[0xA0, 0x00, 0xD0, 0x05, 0x12, 0x04]).unwrap();
+        let handle = EmulatorHandle::spawn(chip8);
+        handle.tick();
+        let frame = handle.recv_frame().unwrap();
+        assert_eq!((frame.width, frame.height), (64, 32));
+        // the top row of a 0 is four pixels wide
+        assert_eq!(frame.screen[..5], [1, 1, 1, 1, 0]);
+        assert!(!frame.halted);
+
+        handle.reset();
+        handle.snapshot();
+        assert!(handle
+            .recv_frame()
+            .unwrap()
+            .screen
+            .iter()
+            .all(|&pixel| pixel == 0));
+    }
+}